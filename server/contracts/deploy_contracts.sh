@@ -5,13 +5,39 @@ forge script lib/sp1-contracts/contracts/script/deploy/SP1VerifierGatewayGroth16
 --rpc-url http://localhost:8545 --broadcast \
 --private-key 0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80
 
+# verifier, then GST program vkey, then property program vkey (see `cargo run --bin vkey`)
 forge create --rpc-url http://localhost:8545 \
   --private-key 0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80 \
   src/PropertyVerifier.sol:CertificateVerifier --broadcast \
-  --constructor-args 0xEE469e23285a6447851eE1a66e400199aC40f779 0x00a19121185617661899f275d6e1de8c40382a2e2023f5f362712d6dccc16775
+  --constructor-args 0xEE469e23285a6447851eE1a66e400199aC40f779 $GST_PROGRAM_VKEY $PROPERTY_PROGRAM_VKEY
 
-# verifier then programvkey
+# record the certificate verifier address as VERIFIER and the block of its deployment transaction as
+# VERIFIER_BLOCK in deployments/<chain id>.json, where the api server looks them up
+cast receipt --rpc-url http://localhost:8545 $DEPLOY_TX_HASH blockNumber
+
+# approve each extraction template hash printed by `cargo run --bin vkey`
+cast send --rpc-url http://localhost:8545 \
+  --private-key 0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80 \
+  $CONTRACT_ADDRESS "setTemplateApproval(bytes32,bool)" $TEMPLATE_HASH true
+
+# set the trusted issuer root printed by `cargo run --bin vkey`
+cast send --rpc-url http://localhost:8545 \
+  --private-key 0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80 \
+  $CONTRACT_ADDRESS "setTrustedIssuerRoot(bytes32)" $ISSUER_ROOT
+
+# local development with SP1_PROVER=mock: deploy the mock verifier, which accepts the empty proof
+# bytes of mock proofs, and pass its address to the certificate verifier instead of the gateway
+forge create --rpc-url http://localhost:8545 \
+  --private-key 0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80 \
+  lib/sp1-contracts/contracts/src/SP1MockVerifier.sol:SP1MockVerifier --broadcast
+
+forge create --rpc-url http://localhost:8545 \
+  --private-key 0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80 \
+  src/PropertyVerifier.sol:CertificateVerifier --broadcast \
+  --constructor-args $MOCK_VERIFIER $GST_PROGRAM_VKEY $PROPERTY_PROGRAM_VKEY
+
+# verifier, then GST program vkey, then property program vkey (see `cargo run --bin vkey`)
 forge create --rpc-url https://ethereum-sepolia-rpc.publicnode.com \
   --private-key 0x0354321fcc872c69dd207ec478fd6781c9c2e52d086ca3d0646901b0e177cdd3 \
   src/PropertyVerifier.sol:CertificateVerifier --broadcast \
-  --constructor-args 0x397A5f7f3dBd538f23DE225B51f532c34448dA9B 0x00a19121185617661899f275d6e1de8c40382a2e2023f5f362712d6dccc16775
\ No newline at end of file
+  --constructor-args 0x397A5f7f3dBd538f23DE225B51f532c34448dA9B $GST_PROGRAM_VKEY $PROPERTY_PROGRAM_VKEY
\ No newline at end of file
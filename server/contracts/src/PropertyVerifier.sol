@@ -8,7 +8,13 @@ struct PublicValuesStruct {
     string legal_name;
     bool signature_valid;
     bytes32 document_commitment;
+    uint8 commitment_version;
     bytes32 public_key_hash;
+    bytes32 issuer_root;
+    bool trusted_issuer;
+    bytes32 template_hash;
+    address claimant;
+    uint8 error_code;
 }
 
 struct PropertyPublicValuesStruct {
@@ -16,7 +22,14 @@ struct PropertyPublicValuesStruct {
     string owner_name;
     bool signature_valid;
     bytes32 document_commitment;
+    uint8 commitment_version;
+    string property_id;
     bytes32 public_key_hash;
+    bytes32 issuer_root;
+    bool trusted_issuer;
+    bytes32 template_hash;
+    address claimant;
+    uint8 error_code;
 }
 
 /// @title CertificateVerifier.
@@ -45,12 +58,37 @@ contract CertificateVerifier {
     /// @notice Mapping to store verified property ownership certificates
     mapping(bytes32 => bool) public verifiedPropertyCertificates;
 
+    /// @notice Wallet that first claimed each verified GST certificate
+    mapping(bytes32 => address) public certificateClaimants;
+
+    /// @notice Wallet that first claimed each verified property ownership certificate
+    mapping(bytes32 => address) public propertyClaimants;
+
+    /// @notice Marketplace listing each verified property ownership certificate is linked to
+    mapping(bytes32 => string) public propertyListings;
+
+    /// @notice Property ownership certificate backing each marketplace listing
+    mapping(string => bytes32) public listingCertificates;
+
+    /// @notice The account allowed to manage extraction template approvals.
+    address public owner;
+
+    /// @notice Hashes of the extraction templates accepted in proofs.
+    /// @dev The prover chooses the template, so only reviewed issuer layouts may be trusted.
+    mapping(bytes32 => bool) public approvedTemplates;
+
+    /// @notice Merkle root of the signing key hashes of trusted issuers.
+    /// @dev A valid signature only shows that some key signed the document; proofs must also show
+    ///      that the signer is in the issuer registry this root was built from.
+    bytes32 public trustedIssuerRoot;
+
     /// @notice Event emitted when a GST certificate is verified
     event GSTCertificateVerified(
         string indexed gst_number,
         string legal_name,
         bytes32 document_commitment,
-        bytes32 public_key_hash
+        bytes32 public_key_hash,
+        address indexed claimant
     );
 
     /// @notice Event emitted when a Property Ownership certificate is verified
@@ -58,13 +96,42 @@ contract CertificateVerifier {
         string indexed property_number,
         string owner_name,
         bytes32 document_commitment,
-        bytes32 public_key_hash
+        string property_id,
+        bytes32 public_key_hash,
+        address indexed claimant
     );
 
-    constructor(address _verifier, bytes32 _propertyProgramVKey) {
+    /// @notice Event emitted when an extraction template is approved or revoked
+    event TemplateApprovalUpdated(bytes32 indexed template_hash, bool approved);
+
+    /// @notice Event emitted when the trusted issuer root is updated
+    event TrustedIssuerRootUpdated(bytes32 issuer_root);
+
+    modifier onlyOwner() {
+        require(msg.sender == owner, "CertificateVerifier: caller is not the owner");
+        _;
+    }
+
+    constructor(address _verifier, bytes32 _gstProgramVKey, bytes32 _propertyProgramVKey) {
         verifier = _verifier;
-     
+        gstProgramVKey = _gstProgramVKey;
         propertyProgramVKey = _propertyProgramVKey;
+        owner = msg.sender;
+    }
+
+    /// @notice Approve or revoke an extraction template
+    /// @param _templateHash The hash of the extraction template
+    /// @param _approved Whether proofs extracted with the template are accepted
+    function setTemplateApproval(bytes32 _templateHash, bool _approved) external onlyOwner {
+        approvedTemplates[_templateHash] = _approved;
+        emit TemplateApprovalUpdated(_templateHash, _approved);
+    }
+
+    /// @notice Set the Merkle root of the trusted issuer registry
+    /// @param _issuerRoot The root printed by `cargo run --bin vkey`
+    function setTrustedIssuerRoot(bytes32 _issuerRoot) external onlyOwner {
+        trustedIssuerRoot = _issuerRoot;
+        emit TrustedIssuerRootUpdated(_issuerRoot);
     }
 
     /// @notice The entrypoint for verifying the proof of a GST certificate.
@@ -77,6 +144,13 @@ contract CertificateVerifier {
     {
         ISP1Verifier(verifier).verifyProof(gstProgramVKey, _publicValues, _proofBytes);
         PublicValuesStruct memory publicValues = abi.decode(_publicValues, (PublicValuesStruct));
+        require(publicValues.error_code == 0, "CertificateVerifier: document rejected");
+        require(publicValues.signature_valid, "CertificateVerifier: invalid signature");
+        require(approvedTemplates[publicValues.template_hash], "CertificateVerifier: template not approved");
+        require(
+            publicValues.trusted_issuer && publicValues.issuer_root == trustedIssuerRoot,
+            "CertificateVerifier: issuer not trusted"
+        );
         return (
             publicValues.gst_number,
             publicValues.legal_name,
@@ -95,17 +169,33 @@ contract CertificateVerifier {
     {
         ISP1Verifier(verifier).verifyProof(gstProgramVKey, _publicValues, _proofBytes);
         PublicValuesStruct memory publicValues = abi.decode(_publicValues, (PublicValuesStruct));
+        require(publicValues.error_code == 0, "CertificateVerifier: document rejected");
+        require(publicValues.signature_valid, "CertificateVerifier: invalid signature");
+        require(approvedTemplates[publicValues.template_hash], "CertificateVerifier: template not approved");
+        require(
+            publicValues.trusted_issuer && publicValues.issuer_root == trustedIssuerRoot,
+            "CertificateVerifier: issuer not trusted"
+        );
         
+        // A certificate belongs to the first wallet that proved it
+        address claimant = certificateClaimants[publicValues.document_commitment];
+        require(
+            claimant == address(0) || claimant == publicValues.claimant,
+            "CertificateVerifier: certificate claimed by another wallet"
+        );
+
         // Store verification results
         verifiedCertificates[publicValues.document_commitment] = true;
         verifiedPublicKeys[publicValues.public_key_hash] = true;
+        certificateClaimants[publicValues.document_commitment] = publicValues.claimant;
 
         // Emit event
         emit GSTCertificateVerified(
             publicValues.gst_number,
             publicValues.legal_name,
             publicValues.document_commitment,
-            publicValues.public_key_hash
+            publicValues.public_key_hash,
+            publicValues.claimant
         );
 
         return (
@@ -139,6 +229,13 @@ contract CertificateVerifier {
     {
         ISP1Verifier(verifier).verifyProof(propertyProgramVKey, _publicValues, _proofBytes);
         PropertyPublicValuesStruct memory publicValues = abi.decode(_publicValues, (PropertyPublicValuesStruct));
+        require(publicValues.error_code == 0, "CertificateVerifier: document rejected");
+        require(publicValues.signature_valid, "CertificateVerifier: invalid signature");
+        require(approvedTemplates[publicValues.template_hash], "CertificateVerifier: template not approved");
+        require(
+            publicValues.trusted_issuer && publicValues.issuer_root == trustedIssuerRoot,
+            "CertificateVerifier: issuer not trusted"
+        );
         return (
             publicValues.property_number,
             publicValues.owner_name,
@@ -149,6 +246,8 @@ contract CertificateVerifier {
     }
 
     /// @notice Verify Property Ownership certificate and store the verification result
+    /// @dev The claimant committed in the proof is recorded rather than msg.sender, so a
+    ///      relayer can submit on the owner's behalf but cannot redirect the claim.
     /// @param _publicValues The encoded public values.
     /// @param _proofBytes The encoded proof.
     function verifyAndStoreProperty(bytes calldata _publicValues, bytes calldata _proofBytes)
@@ -157,17 +256,49 @@ contract CertificateVerifier {
     {
         ISP1Verifier(verifier).verifyProof(propertyProgramVKey, _publicValues, _proofBytes);
         PropertyPublicValuesStruct memory publicValues = abi.decode(_publicValues, (PropertyPublicValuesStruct));
+        require(publicValues.error_code == 0, "CertificateVerifier: document rejected");
+        require(publicValues.signature_valid, "CertificateVerifier: invalid signature");
+        require(approvedTemplates[publicValues.template_hash], "CertificateVerifier: template not approved");
+        require(
+            publicValues.trusted_issuer && publicValues.issuer_root == trustedIssuerRoot,
+            "CertificateVerifier: issuer not trusted"
+        );
         
+        // A property certificate belongs to the first wallet that proved it
+        address claimant = propertyClaimants[publicValues.document_commitment];
+        require(
+            claimant == address(0) || claimant == publicValues.claimant,
+            "CertificateVerifier: property claimed by another wallet"
+        );
+
+        // A property certificate backs a single marketplace listing, and a listing a single certificate
+        require(bytes(publicValues.property_id).length > 0, "CertificateVerifier: missing property id");
+        string memory listing = propertyListings[publicValues.document_commitment];
+        require(
+            bytes(listing).length == 0 || keccak256(bytes(listing)) == keccak256(bytes(publicValues.property_id)),
+            "CertificateVerifier: property linked to another listing"
+        );
+        bytes32 listingCertificate = listingCertificates[publicValues.property_id];
+        require(
+            listingCertificate == bytes32(0) || listingCertificate == publicValues.document_commitment,
+            "CertificateVerifier: listing backed by another property"
+        );
+
         // Store verification results
         verifiedPropertyCertificates[publicValues.document_commitment] = true;
         verifiedPublicKeys[publicValues.public_key_hash] = true;
+        propertyClaimants[publicValues.document_commitment] = publicValues.claimant;
+        propertyListings[publicValues.document_commitment] = publicValues.property_id;
+        listingCertificates[publicValues.property_id] = publicValues.document_commitment;
 
         // Emit event
         emit PropertyCertificateVerified(
             publicValues.property_number,
             publicValues.owner_name,
             publicValues.document_commitment,
-            publicValues.public_key_hash
+            publicValues.property_id,
+            publicValues.public_key_hash,
+            publicValues.claimant
         );
 
         return (
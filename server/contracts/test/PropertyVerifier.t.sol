@@ -3,8 +3,14 @@ pragma solidity ^0.8.20;
 
 import {Test, console} from "forge-std/Test.sol";
 import {stdJson} from "forge-std/StdJson.sol";
-import {CertificateVerifier} from "../src/PropertyVerifier.sol";
+import {stdError} from "forge-std/StdError.sol";
+import {
+    CertificateVerifier,
+    PublicValuesStruct,
+    PropertyPublicValuesStruct
+} from "../src/PropertyVerifier.sol";
 import {SP1VerifierGateway} from "sp1-contracts/SP1VerifierGateway.sol";
+import {SP1MockVerifier} from "sp1-contracts/SP1MockVerifier.sol";
 
 struct SP1ProofFixtureJson {
     string gstNumber;
@@ -49,6 +55,9 @@ contract CertificateVerifierGroth16Test is Test {
         verifier = address(new SP1VerifierGateway(address(1)));
         certificateVerifier = new CertificateVerifier(
             verifier,
+            bytes32(
+                0x00506fd89abddc3ed51a6a3a5e0f7bd53b484e2877ae0df10969838991cff1f8
+            ),
             bytes32(
                 0x00506fd89abddc3ed51a6a3a5e0f7bd53b484e2877ae0df10969838991cff1f9
             )
@@ -89,6 +98,37 @@ contract CertificateVerifierGroth16Test is Test {
         // by directly testing the contract state
     }
 
+    function test_SetTemplateApproval() public {
+        bytes32 templateHash = bytes32(uint256(0x789));
+        assert(certificateVerifier.approvedTemplates(templateHash) == false);
+
+        certificateVerifier.setTemplateApproval(templateHash, true);
+        assert(certificateVerifier.approvedTemplates(templateHash) == true);
+
+        certificateVerifier.setTemplateApproval(templateHash, false);
+        assert(certificateVerifier.approvedTemplates(templateHash) == false);
+    }
+
+    function testRevert_SetTemplateApprovalNotOwner() public {
+        vm.expectRevert("CertificateVerifier: caller is not the owner");
+        vm.prank(address(0xBEEF));
+        certificateVerifier.setTemplateApproval(bytes32(uint256(0x789)), true);
+    }
+
+    function test_SetTrustedIssuerRoot() public {
+        bytes32 issuerRoot = bytes32(uint256(0xabc));
+        assert(certificateVerifier.trustedIssuerRoot() == bytes32(0));
+
+        certificateVerifier.setTrustedIssuerRoot(issuerRoot);
+        assert(certificateVerifier.trustedIssuerRoot() == issuerRoot);
+    }
+
+    function testRevert_SetTrustedIssuerRootNotOwner() public {
+        vm.expectRevert("CertificateVerifier: caller is not the owner");
+        vm.prank(address(0xBEEF));
+        certificateVerifier.setTrustedIssuerRoot(bytes32(uint256(0xabc)));
+    }
+
     function testRevert_InvalidGSTProof() public {
         vm.expectRevert();
 
@@ -138,6 +178,9 @@ contract GSTVerifierPlonkTest is Test {
         verifier = address(new SP1VerifierGateway(address(1)));
         certificateVerifier = new CertificateVerifier(
             verifier,
+            bytes32(
+                0x00506fd89abddc3ed51a6a3a5e0f7bd53b484e2877ae0df10969838991cff1f8
+            ),
             bytes32(
                 0x00506fd89abddc3ed51a6a3a5e0f7bd53b484e2877ae0df10969838991cff1f9
             )
@@ -205,3 +248,203 @@ contract GSTVerifierPlonkTest is Test {
         certificateVerifier.verifyGSTProof(mockPublicValues, mockProof);
     }
 }
+
+/// @dev Stores proofs through `SP1MockVerifier`, which accepts any public values with an empty
+///      proof, to test the checks the contract makes on the public values.
+contract CertificateVerifierStorageTest is Test {
+    bytes32 constant TEMPLATE_HASH = bytes32(uint256(0x789));
+    bytes32 constant ISSUER_ROOT = bytes32(uint256(0xabc));
+    bytes32 constant COMMITMENT = bytes32(uint256(0x123));
+    address constant OWNER = address(0xA11CE);
+    address constant OTHER = address(0xB0B);
+
+    CertificateVerifier public certificateVerifier;
+
+    function setUp() public {
+        certificateVerifier = new CertificateVerifier(
+            address(new SP1MockVerifier()),
+            bytes32(
+                0x00506fd89abddc3ed51a6a3a5e0f7bd53b484e2877ae0df10969838991cff1f8
+            ),
+            bytes32(
+                0x00506fd89abddc3ed51a6a3a5e0f7bd53b484e2877ae0df10969838991cff1f9
+            )
+        );
+        certificateVerifier.setTemplateApproval(TEMPLATE_HASH, true);
+        certificateVerifier.setTrustedIssuerRoot(ISSUER_ROOT);
+    }
+
+    function propertyValues(bytes32 commitment, string memory propertyId, address claimant)
+        internal
+        pure
+        returns (PropertyPublicValuesStruct memory)
+    {
+        return PropertyPublicValuesStruct({
+            property_number: "PROP-001",
+            owner_name: "RAVI KUMAR",
+            signature_valid: true,
+            document_commitment: commitment,
+            commitment_version: 1,
+            property_id: propertyId,
+            public_key_hash: bytes32(uint256(0x456)),
+            issuer_root: ISSUER_ROOT,
+            trusted_issuer: true,
+            template_hash: TEMPLATE_HASH,
+            claimant: claimant,
+            error_code: 0
+        });
+    }
+
+    function gstValues(bytes32 commitment, address claimant)
+        internal
+        pure
+        returns (PublicValuesStruct memory)
+    {
+        return PublicValuesStruct({
+            gst_number: "07AAATC0869P1ZB",
+            legal_name: "CONSUMER UNITY AND TRUST SOCIETY",
+            signature_valid: true,
+            document_commitment: commitment,
+            commitment_version: 1,
+            public_key_hash: bytes32(uint256(0x456)),
+            issuer_root: ISSUER_ROOT,
+            trusted_issuer: true,
+            template_hash: TEMPLATE_HASH,
+            claimant: claimant,
+            error_code: 0
+        });
+    }
+
+    function storeProperty(PropertyPublicValuesStruct memory values) internal {
+        certificateVerifier.verifyAndStoreProperty(abi.encode(values), "");
+    }
+
+    function storeGST(PublicValuesStruct memory values) internal {
+        certificateVerifier.verifyAndStoreGST(abi.encode(values), "");
+    }
+
+    function test_VerifyAndStoreProperty() public {
+        storeProperty(propertyValues(COMMITMENT, "listing-1", OWNER));
+
+        assert(certificateVerifier.isPropertyVerified(COMMITMENT));
+        assert(certificateVerifier.propertyClaimants(COMMITMENT) == OWNER);
+        assertEq(certificateVerifier.propertyListings(COMMITMENT), "listing-1");
+        assert(certificateVerifier.listingCertificates("listing-1") == COMMITMENT);
+    }
+
+    function test_VerifyAndStorePropertyAgainForSameClaimant() public {
+        storeProperty(propertyValues(COMMITMENT, "listing-1", OWNER));
+        storeProperty(propertyValues(COMMITMENT, "listing-1", OWNER));
+
+        assert(certificateVerifier.propertyClaimants(COMMITMENT) == OWNER);
+    }
+
+    function testRevert_PropertyClaimedByAnotherWallet() public {
+        storeProperty(propertyValues(COMMITMENT, "listing-1", OWNER));
+
+        vm.expectRevert("CertificateVerifier: property claimed by another wallet");
+        storeProperty(propertyValues(COMMITMENT, "listing-1", OTHER));
+    }
+
+    function testRevert_PropertyLinkedToAnotherListing() public {
+        storeProperty(propertyValues(COMMITMENT, "listing-1", OWNER));
+
+        vm.expectRevert("CertificateVerifier: property linked to another listing");
+        storeProperty(propertyValues(COMMITMENT, "listing-2", OWNER));
+    }
+
+    function testRevert_ListingBackedByAnotherProperty() public {
+        storeProperty(propertyValues(COMMITMENT, "listing-1", OWNER));
+
+        vm.expectRevert("CertificateVerifier: listing backed by another property");
+        storeProperty(propertyValues(bytes32(uint256(0x321)), "listing-1", OTHER));
+    }
+
+    function testRevert_PropertyMissingPropertyId() public {
+        vm.expectRevert("CertificateVerifier: missing property id");
+        storeProperty(propertyValues(COMMITMENT, "", OWNER));
+    }
+
+    function testRevert_PropertyDocumentRejected() public {
+        PropertyPublicValuesStruct memory values = propertyValues(COMMITMENT, "listing-1", OWNER);
+        values.error_code = 1;
+
+        vm.expectRevert("CertificateVerifier: document rejected");
+        storeProperty(values);
+    }
+
+    function testRevert_PropertyInvalidSignature() public {
+        PropertyPublicValuesStruct memory values = propertyValues(COMMITMENT, "listing-1", OWNER);
+        values.signature_valid = false;
+
+        vm.expectRevert("CertificateVerifier: invalid signature");
+        storeProperty(values);
+
+        vm.expectRevert("CertificateVerifier: invalid signature");
+        certificateVerifier.verifyPropertyProof(abi.encode(values), "");
+    }
+
+    function testRevert_PropertyIssuerRootMismatch() public {
+        PropertyPublicValuesStruct memory values = propertyValues(COMMITMENT, "listing-1", OWNER);
+        values.issuer_root = bytes32(uint256(0xdef));
+
+        vm.expectRevert("CertificateVerifier: issuer not trusted");
+        storeProperty(values);
+    }
+
+    function testRevert_PropertyTemplateNotApproved() public {
+        PropertyPublicValuesStruct memory values = propertyValues(COMMITMENT, "listing-1", OWNER);
+        values.template_hash = bytes32(uint256(0x987));
+
+        vm.expectRevert("CertificateVerifier: template not approved");
+        storeProperty(values);
+    }
+
+    function testRevert_PropertyNonEmptyProof() public {
+        bytes memory publicValues = abi.encode(propertyValues(COMMITMENT, "listing-1", OWNER));
+
+        vm.expectRevert(stdError.assertionError);
+        certificateVerifier.verifyAndStoreProperty(publicValues, new bytes(100));
+    }
+
+    function test_VerifyAndStoreGST() public {
+        storeGST(gstValues(COMMITMENT, OWNER));
+
+        assert(certificateVerifier.isDocumentVerified(COMMITMENT));
+        assert(certificateVerifier.certificateClaimants(COMMITMENT) == OWNER);
+    }
+
+    function testRevert_GSTClaimedByAnotherWallet() public {
+        storeGST(gstValues(COMMITMENT, OWNER));
+
+        vm.expectRevert("CertificateVerifier: certificate claimed by another wallet");
+        storeGST(gstValues(COMMITMENT, OTHER));
+    }
+
+    function testRevert_GSTDocumentRejected() public {
+        PublicValuesStruct memory values = gstValues(COMMITMENT, OWNER);
+        values.error_code = 3;
+
+        vm.expectRevert("CertificateVerifier: document rejected");
+        storeGST(values);
+    }
+
+    function testRevert_GSTInvalidSignature() public {
+        PublicValuesStruct memory values = gstValues(COMMITMENT, OWNER);
+        values.signature_valid = false;
+
+        vm.expectRevert("CertificateVerifier: invalid signature");
+        storeGST(values);
+
+        vm.expectRevert("CertificateVerifier: invalid signature");
+        certificateVerifier.verifyGSTProof(abi.encode(values), "");
+    }
+
+    function testRevert_GSTIssuerRootMismatch() public {
+        PublicValuesStruct memory values = gstValues(COMMITMENT, OWNER);
+        values.issuer_root = bytes32(uint256(0xdef));
+
+        vm.expectRevert("CertificateVerifier: issuer not trusted");
+        storeGST(values);
+    }
+}
@@ -0,0 +1,47 @@
+//! GST Certificate Verification Program
+//!
+//! This program verifies GST registration certificate PDFs and extracts key information:
+//! - GST number
+//! - Legal name
+//! - Digital signature validity
+//! - Document commitment hash and commitment scheme version
+//! - Public key hash
+//! - Trusted issuer root and whether the signer is a trusted issuer
+//! - Extraction template hash
+//! - Claimant wallet address
+//! - Error code, zero when the document is verified
+//!
+//! The program runs inside the SP1 zkVM to generate zero-knowledge proofs
+//! that prove the document is valid without revealing sensitive data.
+
+// These two lines are necessary for the program to properly compile.
+//
+// Under the hood, we wrap your main function with some extra code so that it behaves properly
+// inside the zkVM.
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use zkpdf_template_lib::{
+    Claim, DocumentVerifier, ExtractionTemplate, GstVerifier, IssuerMembership,
+};
+
+pub fn main() {
+    // Read PDF bytes as input to the program.
+    let pdf_bytes = sp1_zkvm::io::read::<Vec<u8>>();
+
+    // Read the extraction template describing the certificate layout.
+    let template = sp1_zkvm::io::read::<ExtractionTemplate>();
+
+    // Read the claim binding the proof to the owner's wallet address.
+    let claim = sp1_zkvm::io::read::<Claim>();
+
+    // Read the trusted issuer root and the signer's membership proof.
+    let issuers = sp1_zkvm::io::read::<IssuerMembership>();
+
+    // Verify the GST certificate and encode the public values of the program using GST data.
+    // A rejected document is committed with the code of the error.
+    let bytes = GstVerifier::verify_and_encode_result(pdf_bytes, &template, &claim, &issuers);
+
+    // Commit to the public values of the program.
+    sp1_zkvm::io::commit_slice(&bytes);
+}
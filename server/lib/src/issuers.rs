@@ -0,0 +1,183 @@
+//! Trusted issuer allowlist
+//!
+//! A valid signature only shows that *some* key signed the PDF. The [`IssuerRegistry`] lists the
+//! hashes (`keccak256(public_key)`) of the government signing keys we trust. The zkVM programs
+//! take the Merkle root of the registry and a membership proof as input, and commit both the root
+//! and whether the signer is a member of it.
+//!
+//! The tree hashes sorted pairs (`keccak256(min(a, b) || max(a, b))`), so a proof is just the list
+//! of sibling hashes and the same scheme can be checked on-chain with OpenZeppelin's
+//! `MerkleProof`.
+
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use zkpdf_lib::verify_and_extract;
+
+use crate::utils::GSTVerificationError;
+
+const TRUSTED_ISSUERS: &str = include_str!("../issuers/registry.toml");
+
+/// A trusted signing key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedIssuer {
+    /// Name of the issuing authority, e.g. `Goods and Services Tax Network`.
+    pub name: String,
+    /// `keccak256` of the signer public key, as reported in `public_key_hash`.
+    pub public_key_hash: String,
+    /// Free-form notes, e.g. the certificate subject and validity.
+    #[serde(default)]
+    pub description: String,
+}
+
+/// The registry of trusted signing keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuerRegistry {
+    #[serde(default)]
+    pub issuers: Vec<TrustedIssuer>,
+}
+
+/// The issuer allowlist passed to the zkVM programs: the Merkle root of the trusted key hashes
+/// and the sibling path of the document signer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuerMembership {
+    /// Merkle root of the trusted key hashes.
+    pub root: [u8; 32],
+    /// Sibling hashes from the signer's leaf up to the root. Empty if the signer is not trusted.
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl IssuerRegistry {
+    /// Parse a registry from TOML.
+    pub fn from_toml(input: &str) -> Result<Self, GSTVerificationError> {
+        toml::from_str(input)
+            .map_err(|e| GSTVerificationError::IssuerRegistryParseFailed(e.to_string()))
+    }
+
+    /// The registry shipped with the library.
+    pub fn builtin() -> Self {
+        Self::from_toml(TRUSTED_ISSUERS).expect("built-in issuer registry is valid")
+    }
+
+    /// The key hashes of all trusted issuers.
+    pub fn key_hashes(&self) -> Result<Vec<[u8; 32]>, GSTVerificationError> {
+        self.issuers
+            .iter()
+            .map(|issuer| {
+                issuer
+                    .public_key_hash
+                    .parse::<B256>()
+                    .map(|hash| hash.0)
+                    .map_err(|e| {
+                        GSTVerificationError::IssuerRegistryParseFailed(format!(
+                            "{}: {}",
+                            issuer.name, e
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// Merkle root of the trusted key hashes.
+    pub fn root(&self) -> Result<[u8; 32], GSTVerificationError> {
+        Ok(merkle_root(&self.key_hashes()?))
+    }
+
+    /// The allowlist input for a document signed by the key with the given hash.
+    pub fn membership(
+        &self,
+        public_key_hash: [u8; 32],
+    ) -> Result<IssuerMembership, GSTVerificationError> {
+        let leaves = self.key_hashes()?;
+        Ok(IssuerMembership {
+            root: merkle_root(&leaves),
+            proof: merkle_proof(&leaves, public_key_hash).unwrap_or_default(),
+        })
+    }
+}
+
+impl IssuerMembership {
+    /// Whether the signer key hash is a member of the allowlist.
+    pub fn contains(&self, public_key_hash: [u8; 32]) -> bool {
+        verify_merkle_proof(self.root, public_key_hash, &self.proof)
+    }
+}
+
+/// Hash of the public key a document was signed with, the leaf of the issuer tree.
+pub fn public_key_hash(public_key: &[u8]) -> [u8; 32] {
+    keccak256(public_key).0
+}
+
+/// Verify the signature of a PDF outside the zkVM and return the hash of its signer key, to look
+/// up the signer's membership proof before proving.
+pub fn signer_public_key_hash(pdf_bytes: Vec<u8>) -> Result<[u8; 32], GSTVerificationError> {
+    let verified_content = verify_and_extract(pdf_bytes)
+        .map_err(|e| GSTVerificationError::PdfVerificationFailed(e.to_string()))?;
+    Ok(public_key_hash(&verified_content.signature.public_key))
+}
+
+/// Merkle root of a set of leaves. An empty set has the zero root, which no leaf is a member of.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = sorted_leaves(leaves);
+    if level.is_empty() {
+        return [0u8; 32];
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Membership proof of `leaf` in the tree built from `leaves`, or `None` if it is not a leaf.
+pub fn merkle_proof(leaves: &[[u8; 32]], leaf: [u8; 32]) -> Option<Vec<[u8; 32]>> {
+    let mut level = sorted_leaves(leaves);
+    let mut index = level.iter().position(|candidate| *candidate == leaf)?;
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        // A promoted odd node has no sibling at this level.
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Check a membership proof against a Merkle root.
+pub fn verify_merkle_proof(root: [u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    if root == [0u8; 32] {
+        return false;
+    }
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(node, *sibling));
+    computed == root
+}
+
+fn sorted_leaves(leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let mut leaves = leaves.to_vec();
+    leaves.sort_unstable();
+    leaves.dedup();
+    leaves
+}
+
+/// Hash the nodes of a level pairwise. An odd node at the end is promoted unchanged.
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(*left, *right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut combined = [0u8; 64];
+    combined[..32].copy_from_slice(&first);
+    combined[32..].copy_from_slice(&second);
+    keccak256(combined).0
+}
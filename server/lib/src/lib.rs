@@ -6,12 +6,23 @@
 //! with proper error handling.
 
 use alloy_sol_types::sol;
+use serde::{Deserialize, Serialize};
 
-use zkpdf_lib::{verify_and_extract, PdfSignatureResult};
+use zkpdf_lib::PdfSignatureResult;
 
-use crate::utils::GSTVerificationError;
+use crate::issuers::public_key_hash;
+use crate::utils::{
+    generate_commitment, generate_property_commitment, GSTVerificationError, COMMITMENT_VERSION,
+};
 
+pub mod issuers;
+pub mod template;
 pub mod utils;
+pub mod verifier;
+
+pub use issuers::{IssuerMembership, IssuerRegistry, TrustedIssuer};
+pub use template::{ExtractedFields, ExtractionTemplate, FieldRule, Normalisation};
+pub use verifier::{verify_document, DocumentType, DocumentVerifier};
 
 #[cfg(test)]
 mod tests;
@@ -20,89 +31,215 @@ pub struct GSTCertificate {
     pub gst_number: String,
     pub legal_name: String,
     pub signature: PdfSignatureResult,
+    /// Hash of the extraction template the certificate was extracted with.
+    pub template_hash: [u8; 32],
+}
+
+/// The marketplace claim a proof is bound to. The programs read it next to the document and
+/// commit it in the public values, so a proof cannot be replayed for another account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    /// Wallet address of the account claiming the document.
+    pub claimant: [u8; 20],
+    /// Marketplace listing the document is proven for. Only committed by documents that back
+    /// a listing.
+    pub property_id: String,
 }
 
 pub struct PropertyOwnershipCertificate {
     pub property_number: String,
     pub owner_name: String,
     pub signature: PdfSignatureResult,
+    /// Hash of the extraction template the certificate was extracted with.
+    pub template_hash: [u8; 32],
 }
 
 sol! {
     /// The public values encoded as a struct that can be easily deserialized inside Solidity.
+    /// `error_code` is zero for a verified document and the [`GSTVerificationError::code`] of the
+    /// failure otherwise.
+    #[derive(Default)]
     struct PublicValuesStruct {
         string property_number;
         string owner_name;
         bool signature_valid;
         bytes32 document_commitment;
+        uint8 commitment_version;
+        string property_id;
         bytes32 public_key_hash;
+        bytes32 issuer_root;
+        bool trusted_issuer;
+        bytes32 template_hash;
+        address claimant;
+        uint8 error_code;
+    }
+
+    /// The public values committed by the GST program, matching `PublicValuesStruct` in
+    /// `CertificateVerifier.sol`.
+    #[derive(Default)]
+    struct GSTPublicValuesStruct {
+        string gst_number;
+        string legal_name;
+        bool signature_valid;
+        bytes32 document_commitment;
+        uint8 commitment_version;
+        bytes32 public_key_hash;
+        bytes32 issuer_root;
+        bool trusted_issuer;
+        bytes32 template_hash;
+        address claimant;
+        uint8 error_code;
+    }
+}
+
+/// Verifier for GST registration certificates.
+pub struct GstVerifier;
+
+impl DocumentVerifier for GstVerifier {
+    const DOCUMENT_TYPE: DocumentType = DocumentType::Gst;
+
+    type Certificate = GSTCertificate;
+    type PublicValues = GSTPublicValuesStruct;
+
+    fn from_fields(
+        fields: &ExtractedFields,
+        signature: PdfSignatureResult,
+        template_hash: [u8; 32],
+    ) -> Result<GSTCertificate, GSTVerificationError> {
+        Ok(GSTCertificate {
+            gst_number: fields.require("gst_number")?,
+            legal_name: fields.require("legal_name")?,
+            signature,
+            template_hash,
+        })
+    }
+
+    fn commitment(certificate: &GSTCertificate) -> [u8; 32] {
+        generate_commitment(certificate)
+    }
+
+    fn public_values(
+        certificate: GSTCertificate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> GSTPublicValuesStruct {
+        let document_commitment = Self::commitment(&certificate);
+        let public_key_hash = public_key_hash(&certificate.signature.public_key);
+
+        GSTPublicValuesStruct {
+            gst_number: certificate.gst_number,
+            legal_name: certificate.legal_name,
+            signature_valid: certificate.signature.is_valid,
+            document_commitment: document_commitment.into(),
+            commitment_version: COMMITMENT_VERSION,
+            public_key_hash: public_key_hash.into(),
+            issuer_root: issuers.root.into(),
+            trusted_issuer: certificate.signature.is_valid && issuers.contains(public_key_hash),
+            template_hash: certificate.template_hash.into(),
+            claimant: claim.claimant.into(),
+            error_code: 0,
+        }
+    }
+
+    fn failure_public_values(
+        error: &GSTVerificationError,
+        template: &ExtractionTemplate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> GSTPublicValuesStruct {
+        GSTPublicValuesStruct {
+            issuer_root: issuers.root.into(),
+            template_hash: template.hash().into(),
+            claimant: claim.claimant.into(),
+            error_code: error.code(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Verifier for Property Ownership certificates.
+pub struct PropertyOwnershipVerifier;
+
+impl DocumentVerifier for PropertyOwnershipVerifier {
+    const DOCUMENT_TYPE: DocumentType = DocumentType::PropertyOwnership;
+
+    type Certificate = PropertyOwnershipCertificate;
+    type PublicValues = PublicValuesStruct;
+
+    fn from_fields(
+        fields: &ExtractedFields,
+        signature: PdfSignatureResult,
+        template_hash: [u8; 32],
+    ) -> Result<PropertyOwnershipCertificate, GSTVerificationError> {
+        Ok(PropertyOwnershipCertificate {
+            property_number: fields.require("property_number")?,
+            owner_name: fields.require("owner_name")?,
+            signature,
+            template_hash,
+        })
+    }
+
+    fn commitment(certificate: &PropertyOwnershipCertificate) -> [u8; 32] {
+        generate_property_commitment(certificate)
+    }
+
+    fn public_values(
+        certificate: PropertyOwnershipCertificate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> PublicValuesStruct {
+        let document_commitment = Self::commitment(&certificate);
+        let public_key_hash = public_key_hash(&certificate.signature.public_key);
+
+        PublicValuesStruct {
+            property_number: certificate.property_number,
+            owner_name: certificate.owner_name,
+            signature_valid: certificate.signature.is_valid,
+            document_commitment: document_commitment.into(),
+            commitment_version: COMMITMENT_VERSION,
+            property_id: claim.property_id.clone(),
+            public_key_hash: public_key_hash.into(),
+            issuer_root: issuers.root.into(),
+            trusted_issuer: certificate.signature.is_valid && issuers.contains(public_key_hash),
+            template_hash: certificate.template_hash.into(),
+            claimant: claim.claimant.into(),
+            error_code: 0,
+        }
+    }
+
+    fn failure_public_values(
+        error: &GSTVerificationError,
+        template: &ExtractionTemplate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> PublicValuesStruct {
+        PublicValuesStruct {
+            property_id: claim.property_id.clone(),
+            issuer_root: issuers.root.into(),
+            template_hash: template.hash().into(),
+            claimant: claim.claimant.into(),
+            error_code: error.code(),
+            ..Default::default()
+        }
     }
 }
 
 /// GST Certificate verification function that extracts legal name and GST number
+/// using the built-in GST template
 pub fn verify_gst_certificate(pdf_bytes: Vec<u8>) -> Result<GSTCertificate, GSTVerificationError> {
-    let verified_content = verify_and_extract(pdf_bytes)
-        .map_err(|e| GSTVerificationError::PdfVerificationFailed(e.to_string()))?;
-
-    let full_text = verified_content.pages.join(" ");
-
-    let gst_pattern =
-        regex::Regex::new(r"([0-9]{2}[A-Z]{5}[0-9]{4}[A-Z]{1}[1-9A-Z]{1}[Z]{1}[0-9A-Z]{1})")
-            .map_err(|e| GSTVerificationError::RegexCompilationFailed(e.to_string()))?;
-
-    let gst_number = gst_pattern
-        .captures(&full_text)
-        .and_then(|cap| cap.get(1))
-        .map(|m| m.as_str().to_string())
-        .ok_or(GSTVerificationError::GSTNumberNotFound)?;
-
-    let legal_name_pattern =
-        regex::Regex::new(r"Legal Name\s*([A-Za-z\s&.,]+?)(?:\n|Trade Name|Additional|$)")
-            .map_err(|e| GSTVerificationError::RegexCompilationFailed(e.to_string()))?;
-
-    let legal_name = legal_name_pattern
-        .captures(&full_text)
-        .and_then(|cap| cap.get(1))
-        .map(|m| m.as_str().trim().to_string())
-        .ok_or(GSTVerificationError::LegalNameNotFound)?;
-
-    Ok(GSTCertificate {
-        gst_number,
-        legal_name,
-        signature: verified_content.signature,
-    })
+    GstVerifier::verify(
+        pdf_bytes,
+        &ExtractionTemplate::default_for(DocumentType::Gst),
+    )
 }
 
 /// Property Ownership Certificate verification function that extracts property number and owner name
+/// using the built-in Property Ownership template
 pub fn verify_property_ownership_certificate(
     pdf_bytes: Vec<u8>,
 ) -> Result<PropertyOwnershipCertificate, GSTVerificationError> {
-    let verified_content = verify_and_extract(pdf_bytes)
-        .map_err(|e| GSTVerificationError::PdfVerificationFailed(e.to_string()))?;
-
-    let full_text = verified_content.pages.join(" ");
-
-    // Pattern to match "The property no: X belong to Y" or similar variations
-    let property_pattern = regex::Regex::new(r"(?im)(?:the\s+)?property\s*:?\s*(?:no\.?:?\s*)?([^\s]+)\s+belong[s]?\s+to\s+(.+?)(?:\.$|\n|$)")
-        .map_err(|e| GSTVerificationError::RegexCompilationFailed(e.to_string()))?;
-
-    let captures = property_pattern
-        .captures(&full_text)
-        .ok_or(GSTVerificationError::PropertyInfoNotFound)?;
-
-    let property_number = captures
-        .get(1)
-        .map(|m| m.as_str().trim().to_string())
-        .ok_or(GSTVerificationError::PropertyNumberNotFound)?;
-
-    let owner_name = captures
-        .get(2)
-        .map(|m| m.as_str().trim().to_string())
-        .ok_or(GSTVerificationError::OwnerNameNotFound)?;
-
-    Ok(PropertyOwnershipCertificate {
-        property_number,
-        owner_name,
-        signature: verified_content.signature,
-    })
+    PropertyOwnershipVerifier::verify(
+        pdf_bytes,
+        &ExtractionTemplate::default_for(DocumentType::PropertyOwnership),
+    )
 }
@@ -0,0 +1,194 @@
+//! Declarative extraction templates
+//!
+//! An [`ExtractionTemplate`] describes how to pull named fields out of the verified text of a
+//! PDF: a regex pattern per field, which capture group holds the value, whether the field is
+//! required, and how the value is normalised. Templates are plain data (TOML or JSON), are passed
+//! to the zkVM program as input, and their hash is committed in the public values so that new
+//! issuer layouts can be onboarded without rebuilding the program.
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::keccak256;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{write_length_prefixed, GSTVerificationError};
+use crate::DocumentType;
+
+const GST_TEMPLATE: &str = include_str!("../templates/gst.toml");
+const PROPERTY_OWNERSHIP_TEMPLATE: &str = include_str!("../templates/property_ownership.toml");
+
+/// A set of field extraction rules for one issuer layout of a document type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionTemplate {
+    /// Unique identifier of the template, e.g. `property_ownership/default`.
+    pub id: String,
+    /// Name of the document type the template extracts, see [`DocumentType::name`].
+    pub document_type: String,
+    /// Free-form description of the issuer layout. Not part of the template hash.
+    #[serde(default)]
+    pub description: String,
+    /// The fields to extract.
+    pub fields: Vec<FieldRule>,
+}
+
+/// How to extract a single named field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldRule {
+    /// Name of the field, e.g. `owner_name`.
+    pub name: String,
+    /// Regex pattern matched against the full text of the document.
+    pub pattern: String,
+    /// Capture group holding the value.
+    #[serde(default = "default_group")]
+    pub group: u32,
+    /// Whether extraction fails when the field is missing.
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Normalisation steps applied to the captured value, in order.
+    #[serde(default)]
+    pub normalise: Vec<Normalisation>,
+}
+
+/// A normalisation step applied to an extracted value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalisation {
+    Trim,
+    CollapseWhitespace,
+    Uppercase,
+    Lowercase,
+    StripTrailingPeriod,
+}
+
+/// The values extracted from a document, keyed by field name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractedFields(BTreeMap<String, String>);
+
+fn default_group() -> u32 {
+    1
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl ExtractionTemplate {
+    /// Parse a template from TOML.
+    pub fn from_toml(input: &str) -> Result<Self, GSTVerificationError> {
+        toml::from_str(input).map_err(|e| GSTVerificationError::TemplateParseFailed(e.to_string()))
+    }
+
+    /// Parse a template from JSON.
+    pub fn from_json(input: &str) -> Result<Self, GSTVerificationError> {
+        serde_json::from_str(input)
+            .map_err(|e| GSTVerificationError::TemplateParseFailed(e.to_string()))
+    }
+
+    /// The built-in template for a document type.
+    pub fn default_for(document_type: DocumentType) -> Self {
+        let input = match document_type {
+            DocumentType::Gst => GST_TEMPLATE,
+            DocumentType::PropertyOwnership => PROPERTY_OWNERSHIP_TEMPLATE,
+        };
+        Self::from_toml(input).expect("built-in extraction template is valid")
+    }
+
+    /// Check that the template extracts the given document type.
+    pub fn check_document_type(
+        &self,
+        document_type: DocumentType,
+    ) -> Result<(), GSTVerificationError> {
+        if self.document_type != document_type.name() {
+            return Err(GSTVerificationError::TemplateDocumentTypeMismatch(
+                self.document_type.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Hash of the template, committed in the public values.
+    ///
+    /// Covers every value that influences extraction, each length-prefixed so that the
+    /// encoding is unambiguous. The description is left out.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut encoded = Vec::new();
+        write_length_prefixed(&mut encoded, self.id.as_bytes());
+        write_length_prefixed(&mut encoded, self.document_type.as_bytes());
+        encoded.extend_from_slice(&(self.fields.len() as u32).to_be_bytes());
+        for field in &self.fields {
+            write_length_prefixed(&mut encoded, field.name.as_bytes());
+            write_length_prefixed(&mut encoded, field.pattern.as_bytes());
+            encoded.extend_from_slice(&field.group.to_be_bytes());
+            encoded.push(field.required as u8);
+            encoded.extend_from_slice(&(field.normalise.len() as u32).to_be_bytes());
+            encoded.extend(field.normalise.iter().map(|step| *step as u8));
+        }
+
+        keccak256(&encoded).0
+    }
+
+    /// Extract the template's fields from the full text of a document.
+    pub fn extract(&self, full_text: &str) -> Result<ExtractedFields, GSTVerificationError> {
+        let mut fields = BTreeMap::new();
+
+        for rule in &self.fields {
+            let pattern = regex::Regex::new(&rule.pattern)
+                .map_err(|e| GSTVerificationError::RegexCompilationFailed(e.to_string()))?;
+
+            let value = pattern
+                .captures(full_text)
+                .and_then(|cap| cap.get(rule.group as usize))
+                .map(|m| rule.normalise(m.as_str()))
+                .filter(|value| !value.is_empty());
+
+            match value {
+                Some(value) => {
+                    fields.insert(rule.name.clone(), value);
+                }
+                None if rule.required => {
+                    return Err(GSTVerificationError::missing_field(&rule.name));
+                }
+                None => {}
+            }
+        }
+
+        Ok(ExtractedFields(fields))
+    }
+}
+
+impl FieldRule {
+    /// Apply the normalisation steps of the rule to a captured value.
+    pub fn normalise(&self, value: &str) -> String {
+        self.normalise
+            .iter()
+            .fold(value.to_string(), |value, step| step.apply(&value))
+    }
+}
+
+impl Normalisation {
+    fn apply(self, value: &str) -> String {
+        match self {
+            Normalisation::Trim => value.trim().to_string(),
+            Normalisation::CollapseWhitespace => {
+                value.split_whitespace().collect::<Vec<_>>().join(" ")
+            }
+            Normalisation::Uppercase => value.to_uppercase(),
+            Normalisation::Lowercase => value.to_lowercase(),
+            Normalisation::StripTrailingPeriod => value.trim_end_matches('.').to_string(),
+        }
+    }
+}
+
+impl ExtractedFields {
+    /// Get an extracted value by field name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// Get a value that must be present for the document type.
+    pub fn require(&self, name: &str) -> Result<String, GSTVerificationError> {
+        self.get(name)
+            .map(str::to_string)
+            .ok_or_else(|| GSTVerificationError::missing_field(name))
+    }
+}
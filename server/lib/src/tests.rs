@@ -1,5 +1,9 @@
-
-use crate::{GSTCertificate, PropertyOwnershipCertificate};
+use crate::utils::GSTVerificationError;
+use crate::{
+    Claim, DocumentType, DocumentVerifier, ExtractionTemplate, GSTCertificate, GstVerifier,
+    IssuerMembership, IssuerRegistry, Normalisation, PropertyOwnershipCertificate,
+    PropertyOwnershipVerifier, TrustedIssuer,
+};
 
 #[test]
 fn test_property_ownership_verification() {
@@ -99,12 +103,14 @@ fn test_commitment_generation() {
         gst_number: "07AAATC0869P1ZB".to_string(),
         legal_name: "Test Company".to_string(),
         signature: mock_signature.clone(),
+        template_hash: [0u8; 32],
     };
 
     let property_cert = PropertyOwnershipCertificate {
         property_number: "1".to_string(),
         owner_name: "Mr. Ajay".to_string(),
         signature: mock_signature,
+        template_hash: [0u8; 32],
     };
 
     // Test that commitment generation works
@@ -118,3 +124,446 @@ fn test_commitment_generation() {
     assert_eq!(gst_commitment.len(), 32);
     assert_eq!(property_commitment.len(), 32);
 }
+
+#[test]
+fn test_document_type_registry() {
+    for document_type in DocumentType::ALL {
+        assert_eq!(
+            DocumentType::from_id(document_type.id()),
+            Some(document_type)
+        );
+        assert_eq!(
+            DocumentType::from_name(document_type.name()),
+            Some(document_type)
+        );
+    }
+    assert_eq!(DocumentType::from_id(0), None);
+
+    assert_eq!(GstVerifier::DOCUMENT_TYPE, DocumentType::Gst);
+    assert_eq!(
+        PropertyOwnershipVerifier::DOCUMENT_TYPE,
+        DocumentType::PropertyOwnership
+    );
+}
+
+#[test]
+fn test_document_verifier_from_template_fields() {
+    use crate::utils::generate_property_commitment;
+    use zkpdf_lib::PdfSignatureResult;
+
+    let mock_signature = PdfSignatureResult {
+        message_digest: vec![1, 2, 3, 4, 5],
+        public_key: vec![6, 7, 8, 9, 10],
+        is_valid: true,
+    };
+
+    let template = ExtractionTemplate::default_for(DocumentType::PropertyOwnership);
+    let fields = template
+        .extract("Property Document\nThe property no: 1 belong to Mr. Ajay.\nEnd of document")
+        .unwrap();
+
+    let property_cert =
+        PropertyOwnershipVerifier::from_fields(&fields, mock_signature.clone(), template.hash())
+            .unwrap();
+    assert_eq!(property_cert.property_number, "1");
+    assert_eq!(property_cert.owner_name, "Mr. Ajay");
+
+    let commitment = generate_property_commitment(&property_cert);
+    let claim = Claim {
+        claimant: [0xAB; 20],
+        property_id: "42".to_string(),
+    };
+    let public_values = PropertyOwnershipVerifier::public_values(
+        property_cert,
+        &claim,
+        &IssuerMembership::default(),
+    );
+    assert_eq!(public_values.document_commitment.0, commitment);
+    assert_eq!(public_values.template_hash.0, template.hash());
+    assert_eq!(public_values.claimant.into_array(), claim.claimant);
+    assert_eq!(public_values.property_id, "42");
+    assert!(public_values.signature_valid);
+    assert!(!public_values.trusted_issuer);
+
+    let gst_template = ExtractionTemplate::default_for(DocumentType::Gst);
+    assert!(matches!(
+        gst_template.extract("No registration details here"),
+        Err(GSTVerificationError::GSTNumberNotFound)
+    ));
+}
+
+#[test]
+fn test_default_templates_match_document_types() {
+    for document_type in DocumentType::ALL {
+        let template = ExtractionTemplate::default_for(document_type);
+        assert!(template.check_document_type(document_type).is_ok());
+    }
+
+    let template = ExtractionTemplate::default_for(DocumentType::Gst);
+    assert!(matches!(
+        template.check_document_type(DocumentType::PropertyOwnership),
+        Err(GSTVerificationError::TemplateDocumentTypeMismatch(_))
+    ));
+}
+
+#[test]
+fn test_labelled_property_template() {
+    let template = ExtractionTemplate::from_toml(include_str!(
+        "../templates/property_ownership_labelled.toml"
+    ))
+    .unwrap();
+
+    let fields = template
+        .extract("Record of Rights\nSurvey No: 12/4a\nOwner's Name:  Smt.  Lakshmi   Devi.\n")
+        .unwrap();
+    assert_eq!(fields.get("property_number"), Some("12/4A"));
+    assert_eq!(fields.get("owner_name"), Some("Smt. Lakshmi Devi"));
+
+    assert!(matches!(
+        template.extract("Owner Name: Lakshmi Devi"),
+        Err(GSTVerificationError::PropertyNumberNotFound)
+    ));
+}
+
+#[test]
+fn test_template_hash() {
+    let template = ExtractionTemplate::default_for(DocumentType::PropertyOwnership);
+
+    // The same template parsed from JSON hashes identically
+    let json = serde_json::to_string(&template).unwrap();
+    let from_json = ExtractionTemplate::from_json(&json).unwrap();
+    assert_eq!(from_json.hash(), template.hash());
+
+    // The description does not affect extraction and is not hashed
+    let mut described = template.clone();
+    described.description = "Another description".to_string();
+    assert_eq!(described.hash(), template.hash());
+
+    // Any extraction rule change produces a different hash
+    let mut optional = template.clone();
+    optional.fields[1].required = false;
+    assert_ne!(optional.hash(), template.hash());
+
+    let mut normalised = template.clone();
+    normalised.fields[1]
+        .normalise
+        .push(Normalisation::Uppercase);
+    assert_ne!(normalised.hash(), template.hash());
+}
+
+#[test]
+fn test_issuer_merkle_proofs() {
+    use crate::issuers::{merkle_proof, merkle_root, verify_merkle_proof};
+
+    let leaves: Vec<[u8; 32]> = (1..=5u8).map(|i| [i; 32]).collect();
+    let root = merkle_root(&leaves);
+
+    // Every leaf, including the promoted odd one, has a valid proof
+    for leaf in &leaves {
+        let proof = merkle_proof(&leaves, *leaf).unwrap();
+        assert!(verify_merkle_proof(root, *leaf, &proof));
+    }
+
+    // The root does not depend on the order of the leaves
+    let mut reversed = leaves.clone();
+    reversed.reverse();
+    assert_eq!(merkle_root(&reversed), root);
+
+    // Non-members have no proof and cannot reuse another leaf's proof
+    let outsider = [9u8; 32];
+    assert!(merkle_proof(&leaves, outsider).is_none());
+    let proof = merkle_proof(&leaves, leaves[0]).unwrap();
+    assert!(!verify_merkle_proof(root, outsider, &proof));
+
+    // A single leaf is its own root, and the empty tree has no members
+    assert_eq!(merkle_root(&leaves[..1]), leaves[0]);
+    assert!(verify_merkle_proof(leaves[0], leaves[0], &[]));
+    assert_eq!(merkle_root(&[]), [0u8; 32]);
+    assert!(!verify_merkle_proof([0u8; 32], [0u8; 32], &[]));
+}
+
+#[test]
+fn test_trusted_issuer_public_values() {
+    use crate::issuers::public_key_hash;
+    use alloy_primitives::B256;
+    use zkpdf_lib::PdfSignatureResult;
+
+    let government_key = vec![1, 2, 3];
+    let self_signed_key = vec![4, 5, 6];
+    let registry = IssuerRegistry {
+        issuers: vec![
+            TrustedIssuer {
+                name: "Registrar".to_string(),
+                public_key_hash: B256::from(public_key_hash(&government_key)).to_string(),
+                description: String::new(),
+            },
+            TrustedIssuer {
+                name: "Other Registrar".to_string(),
+                public_key_hash: B256::repeat_byte(7).to_string(),
+                description: String::new(),
+            },
+        ],
+    };
+    let root = registry.root().unwrap();
+
+    let certificate = |public_key: Vec<u8>| PropertyOwnershipCertificate {
+        property_number: "1".to_string(),
+        owner_name: "Me".to_string(),
+        signature: PdfSignatureResult {
+            message_digest: vec![1],
+            public_key,
+            is_valid: true,
+        },
+        template_hash: [0u8; 32],
+    };
+
+    let membership = registry
+        .membership(public_key_hash(&government_key))
+        .unwrap();
+    let public_values = PropertyOwnershipVerifier::public_values(
+        certificate(government_key),
+        &Claim::default(),
+        &membership,
+    );
+    assert!(public_values.trusted_issuer);
+    assert_eq!(public_values.issuer_root.0, root);
+
+    // A self-signed document has a valid signature but is not from a trusted issuer
+    let membership = registry
+        .membership(public_key_hash(&self_signed_key))
+        .unwrap();
+    assert_eq!(membership.root, root);
+    let public_values = PropertyOwnershipVerifier::public_values(
+        certificate(self_signed_key),
+        &Claim::default(),
+        &membership,
+    );
+    assert!(public_values.signature_valid);
+    assert!(!public_values.trusted_issuer);
+    assert_eq!(public_values.issuer_root.0, root);
+
+    // The built-in registry parses, and malformed hashes are rejected
+    assert!(IssuerRegistry::builtin().root().is_ok());
+    let malformed =
+        IssuerRegistry::from_toml("[[issuers]]\nname = \"Broken\"\npublic_key_hash = \"0x1234\"\n")
+            .unwrap();
+    assert!(matches!(
+        malformed.root(),
+        Err(GSTVerificationError::IssuerRegistryParseFailed(_))
+    ));
+}
+
+#[test]
+fn test_builtin_issuer_registry() {
+    use alloy_primitives::B256;
+
+    // The root approved on-chain with `setTrustedIssuerRoot`. Update it, and the deployed
+    // contracts, whenever `issuers/registry.toml` changes.
+    const ISSUER_ROOT: &str = "0xaf174c33a4628f49a1106ad829c30415627cf8ea6336ed7411f3c327bc25b64f";
+    // Signer key of GST registration certificates, committed in the GST proof fixtures
+    const GSTN_KEY_HASH: &str =
+        "0xaf174c33a4628f49a1106ad829c30415627cf8ea6336ed7411f3c327bc25b64f";
+
+    let registry = IssuerRegistry::builtin();
+    let root = registry.root().unwrap();
+    assert_eq!(B256::from(root).to_string(), ISSUER_ROOT);
+
+    let gstn = GSTN_KEY_HASH.parse::<B256>().unwrap().0;
+    let membership = registry.membership(gstn).unwrap();
+    assert_eq!(membership.root, root);
+    assert!(membership.contains(gstn));
+}
+
+#[test]
+fn test_sample_gst_certificate_signer_is_trusted() {
+    use crate::issuers::signer_public_key_hash;
+
+    let pdf_bytes = include_bytes!("../../samples/GST-certificate.pdf").to_vec();
+    let signer = signer_public_key_hash(pdf_bytes).unwrap();
+
+    let registry = IssuerRegistry::builtin();
+    let membership = registry.membership(signer).unwrap();
+    assert_eq!(membership.root, registry.root().unwrap());
+    assert!(membership.contains(signer));
+}
+
+#[test]
+fn test_invalid_signature_is_not_trusted() {
+    use crate::issuers::public_key_hash;
+    use alloy_primitives::B256;
+    use zkpdf_lib::PdfSignatureResult;
+
+    let government_key = vec![1, 2, 3];
+    let registry = IssuerRegistry {
+        issuers: vec![TrustedIssuer {
+            name: "Registrar".to_string(),
+            public_key_hash: B256::from(public_key_hash(&government_key)).to_string(),
+            description: String::new(),
+        }],
+    };
+    let membership = registry
+        .membership(public_key_hash(&government_key))
+        .unwrap();
+
+    // A document whose signature does not match its content, e.g. because it was edited after
+    // signing, names a trusted key but is not signed by it
+    let signature = || PdfSignatureResult {
+        message_digest: vec![1],
+        public_key: government_key.clone(),
+        is_valid: false,
+    };
+    let property = PropertyOwnershipCertificate {
+        property_number: "1".to_string(),
+        owner_name: "Me".to_string(),
+        signature: signature(),
+        template_hash: [0u8; 32],
+    };
+    let public_values =
+        PropertyOwnershipVerifier::public_values(property, &Claim::default(), &membership);
+    assert!(!public_values.signature_valid);
+    assert!(!public_values.trusted_issuer);
+
+    let gst = GSTCertificate {
+        gst_number: "07AAATC0869P1ZB".to_string(),
+        legal_name: "Me".to_string(),
+        signature: signature(),
+        template_hash: [0u8; 32],
+    };
+    let public_values = GstVerifier::public_values(gst, &Claim::default(), &membership);
+    assert!(!public_values.signature_valid);
+    assert!(!public_values.trusted_issuer);
+}
+
+#[test]
+fn test_commitments_are_unambiguous() {
+    use crate::utils::{generate_commitment, generate_property_commitment, COMMITMENT_VERSION};
+    use zkpdf_lib::PdfSignatureResult;
+
+    let signature = |message_digest: Vec<u8>, public_key: Vec<u8>| PdfSignatureResult {
+        message_digest,
+        public_key,
+        is_valid: true,
+    };
+    let property = |property_number: &str, owner_name: &str, signature: PdfSignatureResult| {
+        PropertyOwnershipCertificate {
+            property_number: property_number.to_string(),
+            owner_name: owner_name.to_string(),
+            signature,
+            template_hash: [0u8; 32],
+        }
+    };
+
+    // Shifting bytes between adjacent fields changes the commitment
+    let base = generate_property_commitment(&property("12", "3", signature(vec![1], vec![2])));
+    let shifted = generate_property_commitment(&property("1", "23", signature(vec![1], vec![2])));
+    assert_ne!(base, shifted);
+
+    // So does shifting bytes between the digest, the fields and the public key
+    let digest_shift =
+        generate_property_commitment(&property("2", "3", signature(vec![1, b'1'], vec![2])));
+    assert_ne!(base, digest_shift);
+    let key_shift =
+        generate_property_commitment(&property("12", "", signature(vec![1], vec![b'3', 2])));
+    assert_ne!(base, key_shift);
+
+    // A GST and a property certificate over the same bytes have different commitments
+    let gst = GSTCertificate {
+        gst_number: "12".to_string(),
+        legal_name: "3".to_string(),
+        signature: signature(vec![1], vec![2]),
+        template_hash: [0u8; 32],
+    };
+    assert_ne!(generate_commitment(&gst), base);
+
+    // The commitment is deterministic and its version is committed in the public values
+    assert_eq!(
+        generate_property_commitment(&property("12", "3", signature(vec![1], vec![2]))),
+        base
+    );
+    let public_values = PropertyOwnershipVerifier::public_values(
+        property("12", "3", signature(vec![1], vec![2])),
+        &Claim::default(),
+        &IssuerMembership::default(),
+    );
+    assert_eq!(public_values.document_commitment.0, base);
+    assert_eq!(public_values.commitment_version, COMMITMENT_VERSION);
+}
+
+#[test]
+fn test_failure_public_values() {
+    use crate::{GSTPublicValuesStruct, PublicValuesStruct};
+    use alloy_sol_types::SolValue;
+
+    let claim = Claim {
+        claimant: [0xAB; 20],
+        property_id: "42".to_string(),
+    };
+    let issuers = IssuerMembership {
+        root: [7u8; 32],
+        proof: Vec::new(),
+    };
+
+    // A document that fails verification is reported in the public values, not as an error
+    let template = ExtractionTemplate::default_for(DocumentType::PropertyOwnership);
+    let bytes = PropertyOwnershipVerifier::verify_and_encode_result(
+        b"not a pdf".to_vec(),
+        &template,
+        &claim,
+        &issuers,
+    );
+    let public_values = PublicValuesStruct::abi_decode(&bytes).unwrap();
+    assert_eq!(public_values.error_code, 1);
+    assert!(!public_values.signature_valid);
+    assert_eq!(public_values.document_commitment.0, [0u8; 32]);
+    assert_eq!(public_values.property_id, "42");
+    assert_eq!(public_values.claimant.into_array(), claim.claimant);
+    assert_eq!(public_values.template_hash.0, template.hash());
+    assert_eq!(public_values.issuer_root.0, issuers.root);
+
+    // A template for another document type is rejected before the PDF is parsed
+    let bytes = GstVerifier::verify_and_encode_result(Vec::new(), &template, &claim, &issuers);
+    let public_values = GSTPublicValuesStruct::abi_decode(&bytes).unwrap();
+    assert_eq!(
+        public_values.error_code,
+        GSTVerificationError::TemplateDocumentTypeMismatch(String::new()).code()
+    );
+    assert_eq!(
+        GSTVerificationError::describe_code(public_values.error_code),
+        "Extraction template is for another document type"
+    );
+}
+
+#[test]
+fn test_error_codes() {
+    let errors = [
+        GSTVerificationError::PdfVerificationFailed(String::new()),
+        GSTVerificationError::RegexCompilationFailed(String::new()),
+        GSTVerificationError::GSTNumberNotFound,
+        GSTVerificationError::LegalNameNotFound,
+        GSTVerificationError::PropertyInfoNotFound,
+        GSTVerificationError::PropertyNumberNotFound,
+        GSTVerificationError::OwnerNameNotFound,
+        GSTVerificationError::FieldNotFound(String::new()),
+        GSTVerificationError::TemplateParseFailed(String::new()),
+        GSTVerificationError::TemplateDocumentTypeMismatch(String::new()),
+        GSTVerificationError::IssuerRegistryParseFailed(String::new()),
+    ];
+
+    // Every error has a distinct, non-zero code with a description
+    let mut codes: Vec<u8> = errors.iter().map(GSTVerificationError::code).collect();
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), errors.len());
+    assert!(!codes.contains(&0));
+    assert_eq!(
+        GSTVerificationError::CODE_DESCRIPTIONS.len(),
+        errors.len() + 1
+    );
+
+    assert_eq!(
+        GSTVerificationError::describe_code(GSTVerificationError::OwnerNameNotFound.code()),
+        GSTVerificationError::OwnerNameNotFound.to_string()
+    );
+    assert_eq!(GSTVerificationError::describe_code(0), "Verified");
+    assert_eq!(GSTVerificationError::describe_code(255), "Unknown error");
+}
@@ -7,28 +7,69 @@ use alloy_primitives::keccak256;
 use std::error::Error;
 use std::fmt;
 
-use crate::{GSTCertificate, PropertyOwnershipCertificate};
+use crate::{DocumentType, GSTCertificate, PropertyOwnershipCertificate};
+
+/// Version of the commitment scheme, committed next to the document commitment.
+pub const COMMITMENT_VERSION: u8 = 1;
 
 /// Generate a commitment hash from the GST certificate data
 pub fn generate_commitment(gst: &GSTCertificate) -> [u8; 32] {
-    let mut combined_input = Vec::new();
-    combined_input.extend_from_slice(&gst.signature.message_digest);
-    combined_input.extend_from_slice(gst.gst_number.as_bytes());
-    combined_input.extend_from_slice(gst.legal_name.as_bytes());
-    combined_input.extend_from_slice(&gst.signature.public_key);
-
-    keccak256(&combined_input).as_slice().try_into().unwrap()
+    document_commitment(
+        DocumentType::Gst,
+        &gst.signature.message_digest,
+        &[gst.gst_number.as_bytes(), gst.legal_name.as_bytes()],
+        &gst.signature.public_key,
+    )
 }
 
 /// Generate a commitment hash from the Property Ownership certificate data
 pub fn generate_property_commitment(property: &PropertyOwnershipCertificate) -> [u8; 32] {
-    let mut combined_input = Vec::new();
-    combined_input.extend_from_slice(&property.signature.message_digest);
-    combined_input.extend_from_slice(property.property_number.as_bytes());
-    combined_input.extend_from_slice(property.owner_name.as_bytes());
-    combined_input.extend_from_slice(&property.signature.public_key);
+    document_commitment(
+        DocumentType::PropertyOwnership,
+        &property.signature.message_digest,
+        &[
+            property.property_number.as_bytes(),
+            property.owner_name.as_bytes(),
+        ],
+        &property.signature.public_key,
+    )
+}
 
-    keccak256(&combined_input).as_slice().try_into().unwrap()
+/// Commit to a signed document under the current [`COMMITMENT_VERSION`].
+///
+/// The preimage is the version byte followed by the domain tag of the document type, the message
+/// digest, each field and the public key, all length-prefixed. Fields can therefore not shift
+/// into each other, and documents of different types never share a commitment.
+pub fn document_commitment(
+    document_type: DocumentType,
+    message_digest: &[u8],
+    fields: &[&[u8]],
+    public_key: &[u8],
+) -> [u8; 32] {
+    let mut combined_input = vec![COMMITMENT_VERSION];
+    write_length_prefixed(&mut combined_input, commitment_domain(document_type));
+    write_length_prefixed(&mut combined_input, message_digest);
+    combined_input.extend_from_slice(&(fields.len() as u32).to_be_bytes());
+    for field in fields {
+        write_length_prefixed(&mut combined_input, field);
+    }
+    write_length_prefixed(&mut combined_input, public_key);
+
+    keccak256(&combined_input).0
+}
+
+/// Domain tag separating the commitments of each document type.
+fn commitment_domain(document_type: DocumentType) -> &'static [u8] {
+    match document_type {
+        DocumentType::Gst => b"zkpdf-template/gst",
+        DocumentType::PropertyOwnership => b"zkpdf-template/property_ownership",
+    }
+}
+
+/// Append `bytes` prefixed with its length as a big-endian `u32`.
+pub(crate) fn write_length_prefixed(encoded: &mut Vec<u8>, bytes: &[u8]) {
+    encoded.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    encoded.extend_from_slice(bytes);
 }
 
 #[derive(Debug)]
@@ -40,6 +81,65 @@ pub enum GSTVerificationError {
     PropertyInfoNotFound,
     PropertyNumberNotFound,
     OwnerNameNotFound,
+    FieldNotFound(String),
+    TemplateParseFailed(String),
+    TemplateDocumentTypeMismatch(String),
+    IssuerRegistryParseFailed(String),
+}
+
+impl GSTVerificationError {
+    /// Error codes committed in the public values, indexed by [`GSTVerificationError::code`].
+    /// Code 0 means the document was verified.
+    pub const CODE_DESCRIPTIONS: [&'static str; 12] = [
+        "Verified",
+        "PDF verification failed",
+        "Regex compilation failed",
+        "GST number not found in PDF",
+        "Legal name not found in PDF",
+        "Property information not found in PDF",
+        "Property number not found in PDF",
+        "Owner name not found in PDF",
+        "Field not found in PDF",
+        "Extraction template parsing failed",
+        "Extraction template is for another document type",
+        "Issuer registry parsing failed",
+    ];
+
+    /// The stable code of the error, committed in the public values when a document is rejected.
+    pub fn code(&self) -> u8 {
+        match self {
+            GSTVerificationError::PdfVerificationFailed(_) => 1,
+            GSTVerificationError::RegexCompilationFailed(_) => 2,
+            GSTVerificationError::GSTNumberNotFound => 3,
+            GSTVerificationError::LegalNameNotFound => 4,
+            GSTVerificationError::PropertyInfoNotFound => 5,
+            GSTVerificationError::PropertyNumberNotFound => 6,
+            GSTVerificationError::OwnerNameNotFound => 7,
+            GSTVerificationError::FieldNotFound(_) => 8,
+            GSTVerificationError::TemplateParseFailed(_) => 9,
+            GSTVerificationError::TemplateDocumentTypeMismatch(_) => 10,
+            GSTVerificationError::IssuerRegistryParseFailed(_) => 11,
+        }
+    }
+
+    /// Describe an error code read from the public values.
+    pub fn describe_code(code: u8) -> &'static str {
+        Self::CODE_DESCRIPTIONS
+            .get(code as usize)
+            .copied()
+            .unwrap_or("Unknown error")
+    }
+
+    /// The error reported when a required template field is missing from the document.
+    pub fn missing_field(name: &str) -> Self {
+        match name {
+            "gst_number" => GSTVerificationError::GSTNumberNotFound,
+            "legal_name" => GSTVerificationError::LegalNameNotFound,
+            "property_number" => GSTVerificationError::PropertyNumberNotFound,
+            "owner_name" => GSTVerificationError::OwnerNameNotFound,
+            _ => GSTVerificationError::FieldNotFound(name.to_string()),
+        }
+    }
 }
 
 impl fmt::Display for GSTVerificationError {
@@ -66,6 +166,22 @@ impl fmt::Display for GSTVerificationError {
             GSTVerificationError::OwnerNameNotFound => {
                 write!(f, "Owner name not found in PDF")
             }
+            GSTVerificationError::FieldNotFound(name) => {
+                write!(f, "Field '{}' not found in PDF", name)
+            }
+            GSTVerificationError::TemplateParseFailed(msg) => {
+                write!(f, "Extraction template parsing failed: {}", msg)
+            }
+            GSTVerificationError::TemplateDocumentTypeMismatch(document_type) => {
+                write!(
+                    f,
+                    "Extraction template is for document type '{}'",
+                    document_type
+                )
+            }
+            GSTVerificationError::IssuerRegistryParseFailed(msg) => {
+                write!(f, "Issuer registry parsing failed: {}", msg)
+            }
         }
     }
 }
@@ -0,0 +1,156 @@
+//! Document verifier abstraction
+//!
+//! Every supported document implements [`DocumentVerifier`], which describes how to turn the
+//! fields extracted from a signed PDF into a certificate, how to commit to it, and how to encode
+//! the public values of the zkVM program. [`DocumentType`] is the registry of supported documents,
+//! keyed by a stable numeric id.
+
+use alloy_sol_types::SolValue;
+
+use zkpdf_lib::{verify_and_extract, PdfSignatureResult};
+
+use crate::utils::GSTVerificationError;
+use crate::{
+    Claim, ExtractedFields, ExtractionTemplate, GstVerifier, IssuerMembership,
+    PropertyOwnershipVerifier,
+};
+
+/// A document that can be verified from a signed PDF and proven inside the zkVM.
+pub trait DocumentVerifier {
+    /// The registry entry for this document.
+    const DOCUMENT_TYPE: DocumentType;
+
+    /// The information extracted from the document.
+    type Certificate;
+
+    /// The public values committed by the zkVM program.
+    type PublicValues: SolValue;
+
+    /// Build the certificate from the fields extracted by a template.
+    fn from_fields(
+        fields: &ExtractedFields,
+        signature: PdfSignatureResult,
+        template_hash: [u8; 32],
+    ) -> Result<Self::Certificate, GSTVerificationError>;
+
+    /// Generate a commitment hash from the certificate data.
+    fn commitment(certificate: &Self::Certificate) -> [u8; 32];
+
+    /// Build the public values for the certificate, bound to the given claim and checked against
+    /// the trusted issuer allowlist.
+    fn public_values(
+        certificate: Self::Certificate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> Self::PublicValues;
+
+    /// Build the public values reporting that the document could not be verified. Only the
+    /// claim, the template and issuer root, and the error code are set.
+    fn failure_public_values(
+        error: &GSTVerificationError,
+        template: &ExtractionTemplate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> Self::PublicValues;
+
+    /// Verify the PDF signature and extract the certificate with the given template.
+    fn verify(
+        pdf_bytes: Vec<u8>,
+        template: &ExtractionTemplate,
+    ) -> Result<Self::Certificate, GSTVerificationError> {
+        template.check_document_type(Self::DOCUMENT_TYPE)?;
+
+        let verified_content = verify_and_extract(pdf_bytes)
+            .map_err(|e| GSTVerificationError::PdfVerificationFailed(e.to_string()))?;
+
+        let full_text = verified_content.pages.join(" ");
+        let fields = template.extract(&full_text)?;
+
+        Self::from_fields(&fields, verified_content.signature, template.hash())
+    }
+
+    /// Verify the PDF and return the ABI-encoded public values.
+    fn verify_and_encode(
+        pdf_bytes: Vec<u8>,
+        template: &ExtractionTemplate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> Result<Vec<u8>, GSTVerificationError> {
+        let certificate = Self::verify(pdf_bytes, template)?;
+        Ok(Self::public_values(certificate, claim, issuers).abi_encode())
+    }
+
+    /// Verify the PDF and return the ABI-encoded public values. A document that fails
+    /// verification is reported through `error_code` rather than an error, so that the zkVM
+    /// program can commit why it was rejected instead of panicking.
+    fn verify_and_encode_result(
+        pdf_bytes: Vec<u8>,
+        template: &ExtractionTemplate,
+        claim: &Claim,
+        issuers: &IssuerMembership,
+    ) -> Vec<u8> {
+        match Self::verify(pdf_bytes, template) {
+            Ok(certificate) => Self::public_values(certificate, claim, issuers).abi_encode(),
+            Err(error) => {
+                Self::failure_public_values(&error, template, claim, issuers).abi_encode()
+            }
+        }
+    }
+}
+
+/// The registry of supported documents, keyed by document-type id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum DocumentType {
+    Gst = 1,
+    PropertyOwnership = 2,
+}
+
+impl DocumentType {
+    /// All registered document types.
+    pub const ALL: [DocumentType; 2] = [DocumentType::Gst, DocumentType::PropertyOwnership];
+
+    /// The stable numeric id of the document type.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Look up a document type by its id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|document_type| document_type.id() == id)
+    }
+
+    /// Look up a document type by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|document_type| document_type.name() == name)
+    }
+
+    /// A short, human readable name for the document type.
+    pub fn name(self) -> &'static str {
+        match self {
+            DocumentType::Gst => "gst",
+            DocumentType::PropertyOwnership => "property_ownership",
+        }
+    }
+}
+
+/// Verify a PDF with the verifier registered for `document_type` and return the ABI-encoded
+/// public values.
+pub fn verify_document(
+    document_type: DocumentType,
+    pdf_bytes: Vec<u8>,
+    template: &ExtractionTemplate,
+    claim: &Claim,
+    issuers: &IssuerMembership,
+) -> Result<Vec<u8>, GSTVerificationError> {
+    match document_type {
+        DocumentType::Gst => GstVerifier::verify_and_encode(pdf_bytes, template, claim, issuers),
+        DocumentType::PropertyOwnership => {
+            PropertyOwnershipVerifier::verify_and_encode(pdf_bytes, template, claim, issuers)
+        }
+    }
+}
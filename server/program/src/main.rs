@@ -4,8 +4,13 @@
 //! - Property number
 //! - Owner name
 //! - Digital signature validity
-//! - Document commitment hash
+//! - Document commitment hash and commitment scheme version
+//! - Marketplace property id
 //! - Public key hash
+//! - Trusted issuer root and whether the signer is a trusted issuer
+//! - Extraction template hash
+//! - Claimant wallet address
+//! - Error code, zero when the document is verified
 //!
 //! The program runs inside the SP1 zkVM to generate zero-knowledge proofs
 //! that prove the document is valid without revealing sensitive data.
@@ -17,11 +22,8 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use alloy_primitives::keccak256;
-use alloy_sol_types::SolType;
-
 use zkpdf_template_lib::{
-    utils::generate_property_commitment, verify_property_ownership_certificate, PublicValuesStruct,
+    Claim, DocumentVerifier, ExtractionTemplate, IssuerMembership, PropertyOwnershipVerifier,
 };
 
 pub fn main() {
@@ -30,28 +32,20 @@ pub fn main() {
     // from the prover.
     let pdf_bytes = sp1_zkvm::io::read::<Vec<u8>>();
 
-    // Verify the Property Ownership certificate and extract information.
-    let property_cert = verify_property_ownership_certificate(pdf_bytes)
-        .expect("Failed to verify Property Ownership certificate");
-
-    // Generate commitment hash using the new function
-    let document_commitment = generate_property_commitment(&property_cert);
-    let public_key_hash = keccak256(&property_cert.signature.public_key);
-
-    // Encode the public values of the program using property data
-    let bytes = PublicValuesStruct::abi_encode(&PublicValuesStruct {
-        property_number: property_cert.property_number,
-        owner_name: property_cert.owner_name,
-        signature_valid: property_cert.signature.is_valid,
-        document_commitment: document_commitment
-            .as_slice()
-            .try_into()
-            .expect("Failed to convert document commitment to FixedBytes"),
-        public_key_hash: public_key_hash
-            .as_slice()
-            .try_into()
-            .expect("Failed to convert public key hash to FixedBytes"),
-    });
+    // Read the extraction template describing the issuer layout. Its hash is committed in the
+    // public values, so the verifier can check which layout the fields were extracted with.
+    let template = sp1_zkvm::io::read::<ExtractionTemplate>();
+
+    // Read the claim binding the proof to the owner's wallet address and marketplace listing.
+    let claim = sp1_zkvm::io::read::<Claim>();
+
+    // Read the trusted issuer root and the signer's membership proof.
+    let issuers = sp1_zkvm::io::read::<IssuerMembership>();
+
+    // Verify the Property Ownership certificate and encode the public values of the program
+    // using property data. A rejected document is committed with the code of the error.
+    let bytes =
+        PropertyOwnershipVerifier::verify_and_encode_result(pdf_bytes, &template, &claim, &issuers);
 
     // Commit to the public values of the program. The final proof will have a commitment to all the
     // bytes that were committed to.
@@ -0,0 +1,48 @@
+//! Admin endpoints
+//!
+//! `GET /webhooks/dead` and `POST /webhooks/:id/retry` expose the status updates of every job and
+//! send them again, so they are only served to requests with an `Authorization: Bearer <token>`
+//! header holding the admin token. The token is read from the `ADMIN_TOKEN` environment variable
+//! (`admin.token_env` setting); without one, the admin endpoints reject every request.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::ApiError;
+
+/// Reject requests that do not present the admin token.
+pub async fn require_token(
+    State(token): State<Option<Arc<str>>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(token) = token else {
+        return Err(ApiError::Unauthorized(
+            "The admin endpoints are disabled: no admin token is set".to_string(),
+        ));
+    };
+    let presented = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => {
+            Ok(next.run(request).await)
+        }
+        _ => Err(ApiError::Unauthorized(
+            "Missing or invalid admin token".to_string(),
+        )),
+    }
+}
+
+/// Compare two secrets in a time that does not depend on where they differ.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,438 @@
+//! Server configuration
+//!
+//! Settings are layered: built-in defaults, then the TOML config file (`--config`, default
+//! `api.toml` if present), then environment variables, then command line flags. The result is
+//! validated at startup so that a misconfigured server fails before accepting requests. See
+//! `api.example.toml` for every setting.
+
+use std::path::{Path, PathBuf};
+
+use alloy::primitives::Address;
+use axum::http::HeaderValue;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use zkpdf_template_script::templates::DEFAULT_TEMPLATES_DIR;
+
+use crate::deployments::DEFAULT_DEPLOYMENTS_DIR;
+use crate::jobs::DEFAULT_MAX_CONCURRENT_JOBS;
+use crate::outbox::DEFAULT_MAX_ATTEMPTS;
+use crate::signer::{SignerSource, DEFAULT_DERIVATION_PATH};
+use crate::store::DEFAULT_JOBS_DB;
+use crate::upload::DEFAULT_MAX_UPLOAD_BYTES;
+use crate::webhook::{DEFAULT_PAYLOAD_VERSION, PAYLOAD_VERSIONS};
+
+/// Config file read when `--config` is not given, if it exists.
+const DEFAULT_CONFIG_PATH: &str = "api.toml";
+
+/// The prover backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverMode {
+    /// Prove on the Succinct prover network. Needs `NETWORK_PRIVATE_KEY`.
+    Network,
+    /// Prove locally on the CPU.
+    Cpu,
+    /// Generate mock proofs, for development and tests.
+    Mock,
+}
+
+impl ProverMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProverMode::Network => "network",
+            ProverMode::Cpu => "cpu",
+            ProverMode::Mock => "mock",
+        }
+    }
+}
+
+/// Command line flags. Each flag can also be set with the environment variable it names, and
+/// overrides the config file.
+#[derive(Parser, Debug)]
+#[command(author, version, about = "zkPDF proving API server", long_about = None)]
+pub struct Args {
+    /// TOML config file.
+    #[arg(long, env = "API_CONFIG")]
+    config: Option<PathBuf>,
+    #[arg(long, env = "PORT")]
+    port: Option<u16>,
+    /// Comma-separated allowed CORS origins, `*` for any.
+    #[arg(long, env = "CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Option<Vec<String>>,
+    #[arg(long, env = "RPC_URL")]
+    rpc_url: Option<String>,
+    /// Chain id the RPC node must report.
+    #[arg(long, env = "CHAIN_ID")]
+    chain_id: Option<u64>,
+    /// Address of the certificate verifier contract, overriding the deployment record.
+    #[arg(long, env = "CONTRACT_ADDRESS")]
+    contract_address: Option<Address>,
+    /// Block the certificate verifier was deployed in, overriding the deployment record.
+    #[arg(long, env = "DEPLOYMENT_BLOCK")]
+    deployment_block: Option<u64>,
+    /// Directory of the `<chain id>.json` deployment records.
+    #[arg(long, env = "DEPLOYMENTS_DIR")]
+    deployments_dir: Option<String>,
+    /// Environment variable holding the private key of the transaction signer.
+    #[arg(long, env = "SIGNER_PRIVATE_KEY_ENV")]
+    signer_private_key_env: Option<String>,
+    /// Encrypted JSON keystore of the transaction signer.
+    #[arg(long, env = "SIGNER_KEYSTORE")]
+    signer_keystore: Option<String>,
+    /// File holding the password of the signer keystore.
+    #[arg(long, env = "SIGNER_PASSWORD_FILE")]
+    signer_password_file: Option<String>,
+    /// Environment variable holding the mnemonic of the transaction signer.
+    #[arg(long, env = "SIGNER_MNEMONIC_ENV")]
+    signer_mnemonic_env: Option<String>,
+    /// Derivation path of the signer key in the mnemonic.
+    #[arg(long, env = "SIGNER_DERIVATION_PATH")]
+    signer_derivation_path: Option<String>,
+    /// Endpoint notified when a property job finishes.
+    #[arg(long, env = "STATUS_UPDATE_ENDPOINT")]
+    status_update_url: Option<String>,
+    /// Environment variable holding the key signing status updates.
+    #[arg(long, env = "WEBHOOK_SECRET_ENV")]
+    webhook_secret_env: Option<String>,
+    /// Environment variable holding the token of the admin endpoints.
+    #[arg(long, env = "ADMIN_TOKEN_ENV")]
+    admin_token_env: Option<String>,
+    /// Attempts before a status update is dead-lettered.
+    #[arg(long, env = "WEBHOOK_MAX_ATTEMPTS")]
+    webhook_max_attempts: Option<u32>,
+    /// Version of the status update payload.
+    #[arg(long, env = "WEBHOOK_PAYLOAD_VERSION")]
+    webhook_payload_version: Option<u8>,
+    #[arg(long, value_enum, env = "SP1_PROVER")]
+    prover: Option<ProverMode>,
+    #[arg(long, env = "MAX_UPLOAD_BYTES")]
+    max_upload_bytes: Option<usize>,
+    #[arg(long, env = "MAX_CONCURRENT_JOBS")]
+    max_concurrent_jobs: Option<usize>,
+    #[arg(long, env = "JOBS_DB")]
+    jobs_db: Option<String>,
+    #[arg(long, env = "TEMPLATES_DIR")]
+    templates_dir: Option<String>,
+}
+
+/// Settings of the server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub port: u16,
+    /// Allowed CORS origins; `*` allows any.
+    pub cors_origins: Vec<String>,
+    pub prover: ProverMode,
+    pub chain: ChainConfig,
+    pub signer: SignerConfig,
+    pub webhooks: WebhookConfig,
+    pub admin: AdminConfig,
+    pub limits: LimitsConfig,
+    pub jobs_db: String,
+    pub templates_dir: String,
+}
+
+/// The chain proofs are stored on.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainConfig {
+    pub rpc_url: String,
+    /// Chain id the RPC node must report, checked at startup.
+    pub chain_id: Option<u64>,
+    /// Address of the certificate verifier contract. When unset it is looked up in the
+    /// deployment record of the chain reported by the RPC node.
+    pub contract_address: Option<Address>,
+    /// Block the contract was deployed in, where the search for its events starts. When unset it
+    /// is looked up in the deployment record, unless `contract_address` is set.
+    pub deployment_block: Option<u64>,
+    /// Directory of the `<chain id>.json` deployment records.
+    pub deployments_dir: String,
+}
+
+/// Where the transaction signer's key comes from; see [`crate::signer`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignerConfig {
+    /// Environment variable holding the private key. Keys are never read from the config file.
+    pub private_key_env: String,
+    /// Encrypted JSON keystore, used instead of the private key.
+    pub keystore: Option<String>,
+    /// File holding the keystore password.
+    pub password_file: Option<String>,
+    /// Environment variable holding a mnemonic, used instead of the private key.
+    pub mnemonic_env: Option<String>,
+    /// Derivation path of the key in the mnemonic.
+    pub derivation_path: String,
+}
+
+/// Status updates; see [`crate::outbox`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Endpoint notified when a property job finishes. No notifications are sent when unset.
+    pub status_update_url: Option<String>,
+    /// Environment variable holding the key signing status updates. Required with an endpoint.
+    pub secret_env: String,
+    /// Attempts before a status update is dead-lettered.
+    pub max_attempts: u32,
+    /// Version of the status update payload; see [`crate::webhook`].
+    pub payload_version: u8,
+}
+
+/// Access to the admin endpoints; see [`crate::admin`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// Environment variable holding the bearer token of the admin endpoints, which are disabled
+    /// when it is unset.
+    pub token_env: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Largest accepted request body, in bytes.
+    pub max_upload_bytes: usize,
+    /// Number of jobs proven at the same time.
+    pub max_concurrent_jobs: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            port: 3000,
+            cors_origins: vec!["*".to_string()],
+            prover: ProverMode::Cpu,
+            chain: ChainConfig::default(),
+            signer: SignerConfig::default(),
+            webhooks: WebhookConfig::default(),
+            admin: AdminConfig::default(),
+            limits: LimitsConfig::default(),
+            jobs_db: DEFAULT_JOBS_DB.to_string(),
+            templates_dir: DEFAULT_TEMPLATES_DIR.to_string(),
+        }
+    }
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: "http://localhost:8545".to_string(),
+            chain_id: None,
+            contract_address: None,
+            deployment_block: None,
+            deployments_dir: DEFAULT_DEPLOYMENTS_DIR.to_string(),
+        }
+    }
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        Self {
+            private_key_env: "PRIVATE_KEY".to_string(),
+            keystore: None,
+            password_file: None,
+            mnemonic_env: None,
+            derivation_path: DEFAULT_DERIVATION_PATH.to_string(),
+        }
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            status_update_url: None,
+            secret_env: "WEBHOOK_SECRET".to_string(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            payload_version: DEFAULT_PAYLOAD_VERSION,
+        }
+    }
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            token_env: "ADMIN_TOKEN".to_string(),
+        }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            max_concurrent_jobs: DEFAULT_MAX_CONCURRENT_JOBS,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from the config file, the environment and the command line, and
+    /// validate it.
+    pub fn load() -> Result<Self, String> {
+        let args = Args::parse();
+
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+        config.apply(args);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read a TOML config file. Settings it omits keep their defaults.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        toml::from_str(&contents)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    /// Override the settings given on the command line or in the environment.
+    fn apply(&mut self, args: Args) {
+        if let Some(port) = args.port {
+            self.port = port;
+        }
+        if let Some(cors_origins) = args.cors_origins {
+            self.cors_origins = cors_origins;
+        }
+        if let Some(rpc_url) = args.rpc_url {
+            self.chain.rpc_url = rpc_url;
+        }
+        if args.chain_id.is_some() {
+            self.chain.chain_id = args.chain_id;
+        }
+        if args.contract_address.is_some() {
+            self.chain.contract_address = args.contract_address;
+        }
+        if args.deployment_block.is_some() {
+            self.chain.deployment_block = args.deployment_block;
+        }
+        if let Some(deployments_dir) = args.deployments_dir {
+            self.chain.deployments_dir = deployments_dir;
+        }
+        if let Some(private_key_env) = args.signer_private_key_env {
+            self.signer.private_key_env = private_key_env;
+        }
+        if args.signer_keystore.is_some() {
+            self.signer.keystore = args.signer_keystore;
+        }
+        if args.signer_password_file.is_some() {
+            self.signer.password_file = args.signer_password_file;
+        }
+        if args.signer_mnemonic_env.is_some() {
+            self.signer.mnemonic_env = args.signer_mnemonic_env;
+        }
+        if let Some(derivation_path) = args.signer_derivation_path {
+            self.signer.derivation_path = derivation_path;
+        }
+        if args.status_update_url.is_some() {
+            self.webhooks.status_update_url = args.status_update_url;
+        }
+        if let Some(secret_env) = args.webhook_secret_env {
+            self.webhooks.secret_env = secret_env;
+        }
+        if let Some(token_env) = args.admin_token_env {
+            self.admin.token_env = token_env;
+        }
+        if let Some(max_attempts) = args.webhook_max_attempts {
+            self.webhooks.max_attempts = max_attempts;
+        }
+        if let Some(payload_version) = args.webhook_payload_version {
+            self.webhooks.payload_version = payload_version;
+        }
+        if let Some(prover) = args.prover {
+            self.prover = prover;
+        }
+        if let Some(max_upload_bytes) = args.max_upload_bytes {
+            self.limits.max_upload_bytes = max_upload_bytes;
+        }
+        if let Some(max_concurrent_jobs) = args.max_concurrent_jobs {
+            self.limits.max_concurrent_jobs = max_concurrent_jobs;
+        }
+        if let Some(jobs_db) = args.jobs_db {
+            self.jobs_db = jobs_db;
+        }
+        if let Some(templates_dir) = args.templates_dir {
+            self.templates_dir = templates_dir;
+        }
+    }
+
+    /// Check the settings that would otherwise only fail in the middle of a request.
+    pub fn validate(&self) -> Result<(), String> {
+        let _ = self.cors()?;
+        self.chain
+            .rpc_url
+            .parse::<reqwest::Url>()
+            .map_err(|e| format!("Invalid RPC URL {}: {}", self.chain.rpc_url, e))?;
+        if let Some(url) = &self.webhooks.status_update_url {
+            url.parse::<reqwest::Url>()
+                .map_err(|e| format!("Invalid status update URL {}: {}", url, e))?;
+            if self.webhook_secret().is_none() {
+                return Err(format!(
+                    "The webhook secret is not set: {} is missing",
+                    self.webhooks.secret_env
+                ));
+            }
+        }
+        if self.webhooks.max_attempts == 0 {
+            return Err("webhooks.max_attempts must be at least 1".to_string());
+        }
+        if !PAYLOAD_VERSIONS.contains(&self.webhooks.payload_version) {
+            return Err(format!(
+                "Unsupported webhooks.payload_version {}; expected one of {:?}",
+                self.webhooks.payload_version, PAYLOAD_VERSIONS
+            ));
+        }
+        if self.limits.max_concurrent_jobs == 0 {
+            return Err("max_concurrent_jobs must be at least 1".to_string());
+        }
+        // Whether a missing key is allowed depends on the chain, checked at startup
+        SignerSource::from_config(&self.signer)?;
+        if self.prover == ProverMode::Network {
+            let key = std::env::var("NETWORK_PRIVATE_KEY").unwrap_or_default();
+            if !(key.starts_with("0x") && key.len() > 10) {
+                return Err("Invalid or missing NETWORK_PRIVATE_KEY".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// The key signing status updates, if set.
+    pub fn webhook_secret(&self) -> Option<Vec<u8>> {
+        std::env::var(&self.webhooks.secret_env)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(String::into_bytes)
+    }
+
+    /// The token of the admin endpoints, if set.
+    pub fn admin_token(&self) -> Option<String> {
+        std::env::var(&self.admin.token_env)
+            .ok()
+            .filter(|token| !token.is_empty())
+    }
+
+    /// The CORS policy allowing the configured origins.
+    pub fn cors(&self) -> Result<CorsLayer, String> {
+        let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+        if self.cors_origins.iter().any(|origin| origin == "*") {
+            return Ok(cors.allow_origin(Any));
+        }
+        let origins = self
+            .cors_origins
+            .iter()
+            .map(|origin| {
+                origin
+                    .parse::<HeaderValue>()
+                    .map_err(|e| format!("Invalid CORS origin {}: {}", origin, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(cors.allow_origin(AllowOrigin::list(origins)))
+    }
+}
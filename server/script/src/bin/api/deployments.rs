@@ -0,0 +1,88 @@
+//! Contract deployments
+//!
+//! Deployed contract addresses are recorded per chain in `contracts/deployments/<chain id>.json`,
+//! a JSON object from contract key to address. The certificate verifier is recorded under
+//! `VERIFIER`, next to the SP1 verifier gateway it uses, and the block it was deployed in under
+//! `VERIFIER_BLOCK`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use alloy::primitives::Address;
+use serde_json::Value;
+
+/// Directory of the deployment records, relative to `server/script`.
+pub const DEFAULT_DEPLOYMENTS_DIR: &str = "../contracts/deployments";
+
+/// Key of the certificate verifier in a deployment record.
+const CERTIFICATE_VERIFIER_KEY: &str = "VERIFIER";
+/// Key of the block the certificate verifier was deployed in.
+const CERTIFICATE_VERIFIER_BLOCK_KEY: &str = "VERIFIER_BLOCK";
+
+/// The deployment record of a chain.
+pub fn deployment_file(dir: impl AsRef<Path>, chain_id: u64) -> PathBuf {
+    dir.as_ref().join(format!("{}.json", chain_id))
+}
+
+/// Look up the certificate verifier deployed on `chain_id`.
+pub fn certificate_verifier_address(
+    dir: impl AsRef<Path>,
+    chain_id: u64,
+) -> Result<Address, String> {
+    let (path, record) = read_record(dir, chain_id)?;
+    let address = record.get(CERTIFICATE_VERIFIER_KEY).ok_or_else(|| {
+        format!(
+            "{} records no {} address",
+            path.display(),
+            CERTIFICATE_VERIFIER_KEY
+        )
+    })?;
+    serde_json::from_value(address.clone()).map_err(|e| {
+        format!(
+            "Invalid {} address in {}: {}",
+            CERTIFICATE_VERIFIER_KEY,
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Look up the block the certificate verifier deployed on `chain_id` was deployed in, if it is
+/// recorded.
+pub fn certificate_verifier_block(
+    dir: impl AsRef<Path>,
+    chain_id: u64,
+) -> Result<Option<u64>, String> {
+    let (path, record) = read_record(dir, chain_id)?;
+    record
+        .get(CERTIFICATE_VERIFIER_BLOCK_KEY)
+        .map(|block| {
+            block.as_u64().ok_or_else(|| {
+                format!(
+                    "Invalid {} in {}: expected a block number",
+                    CERTIFICATE_VERIFIER_BLOCK_KEY,
+                    path.display()
+                )
+            })
+        })
+        .transpose()
+}
+
+fn read_record(
+    dir: impl AsRef<Path>,
+    chain_id: u64,
+) -> Result<(PathBuf, HashMap<String, Value>), String> {
+    let path = deployment_file(dir, chain_id);
+    let contents = std::fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "No deployment recorded for chain {} ({}): {}; deploy the certificate verifier and \
+             record it there, or set contract_address",
+            chain_id,
+            path.display(),
+            e
+        )
+    })?;
+    let record = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid deployment record {}: {}", path.display(), e))?;
+    Ok((path, record))
+}
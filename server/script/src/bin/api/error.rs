@@ -0,0 +1,291 @@
+//! API errors
+//!
+//! Every failure of a request or a job is an [`ApiError`]. It is returned as
+//! `{"code", "message", "details"}` with a matching HTTP status, and recorded in the same shape in
+//! the `error` of a failed job, so that clients can branch on the stable `code`.
+
+use std::fmt;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zkpdf_template_lib::utils::GSTVerificationError;
+
+/// A failed request or job.
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    /// The request is malformed.
+    InvalidRequest(String),
+    /// The request body is over the upload limit.
+    PayloadTooLarge(String),
+    /// The upload is not a PDF.
+    NotAPdf,
+    /// The requested extraction template does not exist or is for another document type.
+    UnknownTemplate(String),
+    /// No job has the requested id.
+    JobNotFound(String),
+    /// The job has not generated a proof.
+    ProofNotFound(String),
+    /// The program rejected the document; `error_code` is the code committed in the public
+    /// values.
+    DocumentRejected { error_code: u8 },
+    /// The proof is bound to another claimant or property than the job.
+    ProofMismatch(String),
+    /// The document is not signed by a key in the trusted issuer registry.
+    UntrustedIssuer,
+    /// The document was extracted with a template the contract does not approve.
+    TemplateNotApproved { template_hash: String },
+    /// The request lacks the admin token.
+    Unauthorized(String),
+    /// The wallet of the request did not sign the claim message of the document.
+    InvalidWalletSignature(String),
+    /// The document is already verified on-chain for another wallet or listing, so a proof of it
+    /// would be rejected.
+    AlreadyVerified {
+        document_commitment: String,
+        reason: String,
+    },
+    /// The listing is backed by another document on-chain, so a proof for it would be rejected.
+    ListingAlreadyBacked {
+        property_id: String,
+        document_commitment: String,
+    },
+    /// The document is verified on-chain, but the transaction that verified it could not be found.
+    RecordLookupFailed {
+        document_commitment: String,
+        cause: String,
+    },
+    /// Simulating a contract call showed that it reverts; `reason` is the decoded revert.
+    CallReverted { call: String, reason: String },
+    /// Executing the program or generating the proof failed.
+    Prover(String),
+    /// The transaction could not be sent, e.g. because the node rejected it.
+    TransactionSendFailed(String),
+    /// The transaction was mined but reverted.
+    TransactionReverted { transaction_hash: String },
+    /// The receipt of a sent transaction could not be fetched.
+    ReceiptUnavailable {
+        transaction_hash: String,
+        cause: String,
+    },
+    /// No webhook delivery has the requested id.
+    DeliveryNotFound(String),
+    /// The status webhook could not be reached or refused the update.
+    Webhook(String),
+    /// The job database failed.
+    Storage(String),
+    /// Anything else going wrong on the server.
+    Internal(String),
+}
+
+impl ApiError {
+    /// The stable machine-readable code of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidRequest(_) => "invalid_request",
+            ApiError::PayloadTooLarge(_) => "payload_too_large",
+            ApiError::NotAPdf => "not_a_pdf",
+            ApiError::UnknownTemplate(_) => "unknown_template",
+            ApiError::JobNotFound(_) => "job_not_found",
+            ApiError::ProofNotFound(_) => "proof_not_found",
+            ApiError::DocumentRejected { .. } => "document_rejected",
+            ApiError::ProofMismatch(_) => "proof_mismatch",
+            ApiError::UntrustedIssuer => "untrusted_issuer",
+            ApiError::TemplateNotApproved { .. } => "template_not_approved",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::InvalidWalletSignature(_) => "invalid_wallet_signature",
+            ApiError::AlreadyVerified { .. } => "already_verified",
+            ApiError::ListingAlreadyBacked { .. } => "listing_already_backed",
+            ApiError::RecordLookupFailed { .. } => "record_lookup_failed",
+            ApiError::CallReverted { .. } => "call_reverted",
+            ApiError::Prover(_) => "prover_error",
+            ApiError::TransactionSendFailed(_) => "transaction_send_failed",
+            ApiError::TransactionReverted { .. } => "transaction_reverted",
+            ApiError::ReceiptUnavailable { .. } => "receipt_unavailable",
+            ApiError::DeliveryNotFound(_) => "delivery_not_found",
+            ApiError::Webhook(_) => "webhook_error",
+            ApiError::Storage(_) => "storage_error",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// The HTTP status the error is returned with.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidRequest(_) | ApiError::UnknownTemplate(_) => StatusCode::BAD_REQUEST,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::NotAPdf => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::Unauthorized(_) | ApiError::InvalidWalletSignature(_) => {
+                StatusCode::UNAUTHORIZED
+            }
+            ApiError::JobNotFound(_)
+            | ApiError::ProofNotFound(_)
+            | ApiError::DeliveryNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::AlreadyVerified { .. } | ApiError::ListingAlreadyBacked { .. } => {
+                StatusCode::CONFLICT
+            }
+            ApiError::DocumentRejected { .. }
+            | ApiError::ProofMismatch(_)
+            | ApiError::UntrustedIssuer
+            | ApiError::TemplateNotApproved { .. }
+            | ApiError::CallReverted { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::TransactionSendFailed(_)
+            | ApiError::TransactionReverted { .. }
+            | ApiError::ReceiptUnavailable { .. }
+            | ApiError::RecordLookupFailed { .. }
+            | ApiError::Webhook(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Prover(_) | ApiError::Storage(_) | ApiError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Structured details of the error, if any.
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            ApiError::JobNotFound(id) | ApiError::ProofNotFound(id) => {
+                Some(json!({ "job_id": id }))
+            }
+            ApiError::DeliveryNotFound(id) => Some(json!({ "delivery_id": id })),
+            ApiError::DocumentRejected { error_code } => Some(json!({ "error_code": error_code })),
+            ApiError::TemplateNotApproved { template_hash } => {
+                Some(json!({ "template_hash": template_hash }))
+            }
+            ApiError::AlreadyVerified {
+                document_commitment,
+                ..
+            }
+            | ApiError::RecordLookupFailed {
+                document_commitment,
+                ..
+            } => Some(json!({ "document_commitment": document_commitment })),
+            ApiError::ListingAlreadyBacked {
+                property_id,
+                document_commitment,
+            } => Some(json!({
+                "property_id": property_id,
+                "document_commitment": document_commitment,
+            })),
+            ApiError::CallReverted { call, reason } => {
+                Some(json!({ "call": call, "reason": reason }))
+            }
+            ApiError::TransactionReverted { transaction_hash } => {
+                Some(json!({ "transaction_hash": transaction_hash }))
+            }
+            ApiError::ReceiptUnavailable {
+                transaction_hash, ..
+            } => Some(json!({ "transaction_hash": transaction_hash })),
+            _ => None,
+        }
+    }
+
+    /// Map the rejection of an axum extractor, keeping its status.
+    pub fn from_rejection(status: StatusCode, message: String) -> Self {
+        match status {
+            StatusCode::PAYLOAD_TOO_LARGE => ApiError::PayloadTooLarge(message),
+            _ => ApiError::InvalidRequest(message),
+        }
+    }
+
+    /// The error as returned to clients and recorded in jobs.
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            code: self.code().to_string(),
+            message: self.to_string(),
+            details: self.details(),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::InvalidRequest(message) | ApiError::PayloadTooLarge(message) => {
+                write!(f, "{}", message)
+            }
+            ApiError::NotAPdf => write!(f, "The uploaded document is not a PDF"),
+            ApiError::UnknownTemplate(message) => write!(f, "{}", message),
+            ApiError::JobNotFound(id) => write!(f, "Unknown job: {}", id),
+            ApiError::ProofNotFound(id) => write!(f, "Job {} has no proof yet", id),
+            ApiError::DocumentRejected { error_code } => write!(
+                f,
+                "Document rejected: {}",
+                GSTVerificationError::describe_code(*error_code)
+            ),
+            ApiError::ProofMismatch(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::InvalidWalletSignature(message) => {
+                write!(f, "{}", message)
+            }
+            ApiError::UntrustedIssuer => {
+                write!(f, "Document is not signed by a trusted issuer")
+            }
+            ApiError::TemplateNotApproved { template_hash } => write!(
+                f,
+                "Extraction template {} is not approved by the contract",
+                template_hash
+            ),
+            ApiError::AlreadyVerified { reason, .. } => {
+                write!(f, "Document is already verified on-chain {}", reason)
+            }
+            ApiError::ListingAlreadyBacked {
+                property_id,
+                document_commitment,
+            } => write!(
+                f,
+                "Listing {} is already backed by document {} on-chain",
+                property_id, document_commitment
+            ),
+            ApiError::RecordLookupFailed {
+                document_commitment,
+                cause,
+            } => write!(
+                f,
+                "Document {} is verified on-chain, but its transaction could not be found: {}",
+                document_commitment, cause
+            ),
+            ApiError::CallReverted { call, reason } => {
+                write!(f, "{} would revert: {}", call, reason)
+            }
+            ApiError::Prover(message) => write!(f, "{}", message),
+            ApiError::TransactionSendFailed(cause) => {
+                write!(f, "Failed to send transaction: {}", cause)
+            }
+            ApiError::TransactionReverted { transaction_hash } => {
+                write!(f, "Transaction {} reverted on-chain", transaction_hash)
+            }
+            ApiError::ReceiptUnavailable {
+                transaction_hash,
+                cause,
+            } => write!(
+                f,
+                "Failed to get the receipt of transaction {}: {}",
+                transaction_hash, cause
+            ),
+            ApiError::DeliveryNotFound(id) => write!(f, "Unknown webhook delivery: {}", id),
+            ApiError::Webhook(message) => write!(f, "Status update failed: {}", message),
+            ApiError::Storage(message) | ApiError::Internal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(self.body())).into_response()
+    }
+}
+
+/// The JSON shape of an [`ApiError`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<Value>,
+}
@@ -0,0 +1,317 @@
+//! Proving jobs
+//!
+//! Proving a document and storing it on-chain takes minutes, so `POST /prove` only queues a job
+//! and returns its id. Jobs run in the background, at most `MAX_CONCURRENT_JOBS` at a time, and
+//! their progress is polled with `GET /jobs/:id`. Every change is saved to the [`JobStore`], so
+//! unfinished jobs are resumed when the server restarts.
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{Address, Bytes};
+use serde::{Deserialize, Serialize, Serializer};
+use sp1_sdk::{SP1ProofMode, SP1ProofWithPublicValues};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+use zkpdf_template_lib::{DocumentType, ExtractionTemplate};
+
+use crate::error::{ApiError, ErrorBody};
+use crate::store::JobStore;
+
+/// Default number of jobs proven at the same time.
+pub const DEFAULT_MAX_CONCURRENT_JOBS: usize = 2;
+
+/// The stage a job is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStage {
+    /// Waiting for a free worker.
+    Queued,
+    /// Executing the program to check the document before proving it.
+    Executing,
+    /// Generating the proof.
+    Proving,
+    /// Submitting the proof to the certificate verifier contract.
+    Submitting,
+    /// Stored on-chain and reported to the status webhook.
+    Notified,
+    /// Stored on-chain but not reported to a status webhook, or proven with a proof system that
+    /// is not verified on-chain.
+    Completed,
+    /// The job failed; see `error`.
+    Failed,
+}
+
+impl JobStage {
+    const ALL: [JobStage; 7] = [
+        JobStage::Queued,
+        JobStage::Executing,
+        JobStage::Proving,
+        JobStage::Submitting,
+        JobStage::Notified,
+        JobStage::Completed,
+        JobStage::Failed,
+    ];
+
+    /// The name of the stage, as reported in the API and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStage::Queued => "queued",
+            JobStage::Executing => "executing",
+            JobStage::Proving => "proving",
+            JobStage::Submitting => "submitting",
+            JobStage::Notified => "notified",
+            JobStage::Completed => "completed",
+            JobStage::Failed => "failed",
+        }
+    }
+
+    /// Look up a stage by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.as_str() == name)
+    }
+
+    /// Whether the job has stopped, successfully or not.
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobStage::Notified | JobStage::Completed | JobStage::Failed
+        )
+    }
+}
+
+/// The kind of proof a job generates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofSystem {
+    /// Groth16 proof, verified on-chain.
+    #[default]
+    Groth16,
+    /// PLONK proof, verified on-chain.
+    Plonk,
+    /// Compressed STARK proof, for off-chain archival and later wrapping.
+    Compressed,
+    /// Uncompressed STARK proof with a shard per segment of the execution.
+    Core,
+}
+
+impl ProofSystem {
+    const ALL: [ProofSystem; 4] = [
+        ProofSystem::Groth16,
+        ProofSystem::Plonk,
+        ProofSystem::Compressed,
+        ProofSystem::Core,
+    ];
+
+    /// The name of the proof system, as used in requests and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProofSystem::Groth16 => "groth16",
+            ProofSystem::Plonk => "plonk",
+            ProofSystem::Compressed => "compressed",
+            ProofSystem::Core => "core",
+        }
+    }
+
+    /// Look up a proof system by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|system| system.as_str() == name)
+    }
+
+    /// The SP1 proof mode generating proofs of this system.
+    pub fn mode(self) -> SP1ProofMode {
+        match self {
+            ProofSystem::Groth16 => SP1ProofMode::Groth16,
+            ProofSystem::Plonk => SP1ProofMode::Plonk,
+            ProofSystem::Compressed => SP1ProofMode::Compressed,
+            ProofSystem::Core => SP1ProofMode::Core,
+        }
+    }
+
+    /// Whether the certificate verifier contract can verify proofs of this system.
+    pub fn is_evm(self) -> bool {
+        matches!(self, ProofSystem::Groth16 | ProofSystem::Plonk)
+    }
+}
+
+/// A proving job, as reported by `GET /jobs/:id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: String,
+    #[serde(serialize_with = "serialize_document_type")]
+    pub document_type: DocumentType,
+    pub stage: JobStage,
+    pub proof_system: ProofSystem,
+    /// Wallet address the proof is bound to.
+    pub claimant: Address,
+    /// Marketplace listing the document is proven for.
+    pub property_id: Option<String>,
+    /// Gas the transaction was estimated to use when it was simulated before sending it.
+    pub gas_estimate: Option<u64>,
+    pub transaction_hash: Option<String>,
+    /// Block the transaction was included in.
+    pub block_number: Option<u64>,
+    /// Gas used by the transaction.
+    pub gas_used: Option<u64>,
+    /// Why the job failed.
+    pub error: Option<ErrorBody>,
+    /// Whether the document was already verified on-chain for the same wallet and listing, in
+    /// which case it was not proven and `transaction_hash` is the transaction that verified it.
+    pub already_verified: bool,
+    /// Whether the outcome was delivered to the status webhook.
+    pub webhook_delivered: bool,
+    /// Why the last attempt to deliver the outcome to the status webhook failed, until an attempt
+    /// succeeds.
+    pub webhook_error: Option<ErrorBody>,
+    /// The public values committed by the proof, once proving has finished. The proof itself is
+    /// served by `GET /jobs/:id/proof`.
+    pub public_values: Option<Bytes>,
+    /// Unix timestamps, in seconds.
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// The document a job proves. Stored until the job finishes.
+pub struct JobInput {
+    pub pdf_bytes: Vec<u8>,
+    pub template: ExtractionTemplate,
+}
+
+/// The jobs of the server and the workers proving them.
+#[derive(Clone)]
+pub struct JobQueue {
+    store: Arc<JobStore>,
+    workers: Arc<Semaphore>,
+}
+
+impl JobQueue {
+    /// A queue saving jobs to `store` and proving at most `max_concurrent_jobs` of them at the
+    /// same time.
+    pub fn new(store: Arc<JobStore>, max_concurrent_jobs: usize) -> Self {
+        Self {
+            store,
+            workers: Arc::new(Semaphore::new(max_concurrent_jobs.max(1))),
+        }
+    }
+
+    /// Register a new queued job.
+    pub fn create(
+        &self,
+        document_type: DocumentType,
+        proof_system: ProofSystem,
+        claimant: Address,
+        property_id: Option<String>,
+        input: JobInput,
+    ) -> Result<Job, String> {
+        let now = unix_time();
+        let job = Job {
+            id: Uuid::new_v4().to_string(),
+            document_type,
+            stage: JobStage::Queued,
+            proof_system,
+            claimant,
+            property_id,
+            gas_estimate: None,
+            transaction_hash: None,
+            block_number: None,
+            gas_used: None,
+            error: None,
+            already_verified: false,
+            webhook_delivered: false,
+            webhook_error: None,
+            public_values: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.store.insert(&job, &input)?;
+        Ok(job)
+    }
+
+    /// Look up a job by id.
+    pub fn get(&self, id: &str) -> Result<Option<Job>, String> {
+        self.store.get(id)
+    }
+
+    /// The document a job proves, until the job finishes.
+    pub fn input(&self, id: &str) -> Result<Option<JobInput>, String> {
+        self.store.input(id)
+    }
+
+    /// The proof generated by a job, once proving has finished.
+    pub fn proof(&self, id: &str) -> Result<Option<SP1ProofWithPublicValues>, String> {
+        self.store.proof(id)
+    }
+
+    /// Save the proof generated by a job and record its public values in the job.
+    pub fn save_proof(&self, id: &str, proof: &SP1ProofWithPublicValues) {
+        if let Err(e) = self.store.save_proof(id, proof) {
+            tracing::error!(job_id = id, error = %e, "failed to save proof");
+            return;
+        }
+        self.update(id, |job| {
+            job.public_values = Some(Bytes::from(proof.public_values.to_vec()))
+        });
+    }
+
+    /// Ids of the jobs to resume at startup; finished jobs are only resumed to `notify` the status
+    /// webhook of their outcome.
+    pub fn resumable(&self, notify: bool) -> Result<Vec<String>, String> {
+        self.store.resumable(notify)
+    }
+
+    /// Apply a change to a job and save it. The document is deleted once the job finishes.
+    pub fn update(&self, id: &str, change: impl FnOnce(&mut Job)) {
+        let result = self.store.get(id).and_then(|job| {
+            let Some(mut job) = job else {
+                return Ok(());
+            };
+            change(&mut job);
+            job.updated_at = unix_time();
+            self.store.save(&job)?;
+            if job.stage.is_finished() {
+                self.store.clear_input(id)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            tracing::error!(job_id = id, error = %e, "failed to save job");
+        }
+    }
+
+    /// Move a job to the next stage.
+    pub fn set_stage(&self, id: &str, stage: JobStage) {
+        self.update(id, |job| job.stage = stage);
+    }
+
+    /// Mark a job as failed.
+    pub fn fail(&self, id: &str, error: &ApiError) {
+        self.update(id, |job| {
+            job.stage = JobStage::Failed;
+            job.error = Some(error.body());
+        });
+    }
+
+    /// Wait for a free worker. The worker is released when the permit is dropped.
+    pub async fn acquire_worker(&self) -> OwnedSemaphorePermit {
+        self.workers
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("job worker semaphore is never closed")
+    }
+}
+
+fn serialize_document_type<S: Serializer>(
+    document_type: &DocumentType,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(document_type.name())
+}
+
+/// The current Unix time, in seconds.
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
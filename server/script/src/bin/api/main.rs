@@ -0,0 +1,1019 @@
+use alloy::{
+    primitives::{Address, Bytes, TxHash, B256},
+    providers::{PendingTransactionBuilder, Provider},
+};
+use alloy_sol_types::SolType;
+use axum::{
+    extract::{DefaultBodyLimit, Path, Request, State},
+    http::StatusCode,
+    middleware,
+    response::Html,
+    routing::{get, post},
+    serve, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::HashableKey;
+use sp1_sdk::{SP1ProofWithPublicValues, SP1Stdin};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower_http::trace::TraceLayer;
+use zkpdf_template_lib::{Claim, DocumentType, GSTPublicValuesStruct, PublicValuesStruct};
+use zkpdf_template_script::{
+    issuer_membership,
+    logging::{self, pii},
+};
+
+use crate::config::Config;
+use crate::error::{ApiError, ErrorBody};
+use crate::jobs::{Job, JobInput, JobQueue, JobStage, ProofSystem};
+use crate::outbox::{Delivery, WebhookOutbox};
+use crate::state::AppState;
+use crate::store::JobStore;
+use crate::upload::Upload;
+use crate::verified::find_verified_property;
+
+mod admin;
+mod config;
+mod deployments;
+mod error;
+mod jobs;
+mod outbox;
+mod ownership;
+mod preflight;
+mod signer;
+mod state;
+mod store;
+mod upload;
+mod verified;
+mod webhook;
+
+#[cfg(test)]
+mod tests;
+
+/// How long a sent transaction may take to be mined before its job fails, so that a dropped or
+/// stuck transaction does not hold a worker forever.
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Fields of a property document upload.
+#[derive(Deserialize)]
+struct ProofRequest {
+    property_id: String,
+    /// Wallet address of the owner claiming the property; committed in the proof.
+    wallet_address: Address,
+    /// Signature of the claim message of the document by the wallet, required to prove it.
+    #[serde(default)]
+    wallet_signature: Option<String>,
+    /// Id of the extraction template matching the issuer layout of the document.
+    #[serde(default)]
+    template: Option<String>,
+    /// Kind of proof to generate. Only Groth16 and PLONK proofs are stored on-chain.
+    #[serde(default)]
+    proof_system: ProofSystem,
+}
+
+/// Fields of a GST certificate upload.
+#[derive(Deserialize)]
+struct GstProofRequest {
+    wallet_address: Address,
+    #[serde(default)]
+    wallet_signature: Option<String>,
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default)]
+    proof_system: ProofSystem,
+}
+
+/// Fields of a property document upload for its claim message.
+#[derive(Deserialize)]
+struct CommitmentRequest {
+    property_id: String,
+    #[serde(default)]
+    template: Option<String>,
+}
+
+/// Fields of a GST certificate upload for its claim message.
+#[derive(Deserialize)]
+struct GstCommitmentRequest {
+    #[serde(default)]
+    template: Option<String>,
+}
+
+/// The commitment of a document and the message its owner signs to prove it.
+#[derive(Serialize)]
+struct CommitmentResponse {
+    document_type: &'static str,
+    document_commitment: B256,
+    /// The message to sign with `personal_sign` and send in `wallet_signature`.
+    message: String,
+}
+
+/// Outcome of a dry run of a document's program.
+#[derive(Serialize)]
+struct ExecuteResponse {
+    document_type: &'static str,
+    /// The values the proof would commit, or `null` if the program did not complete.
+    public_values: Option<serde_json::Value>,
+    /// Number of RISC-V cycles the program ran for, an estimate of the proving cost.
+    cycles: u64,
+    /// Why the document would be rejected, if it would be.
+    error: Option<ErrorBody>,
+}
+
+#[derive(Serialize)]
+struct VerifyResponse {
+    valid: bool,
+    error: Option<String>,
+}
+
+async fn prove(
+    State(state): State<AppState>,
+    Upload {
+        pdf_bytes,
+        fields: body,
+    }: Upload<ProofRequest>,
+) -> Result<(StatusCode, Json<Job>), ApiError> {
+    let template = state
+        .templates
+        .resolve(DocumentType::PropertyOwnership, body.template.as_deref())
+        .map_err(ApiError::UnknownTemplate)?;
+    let input = JobInput {
+        pdf_bytes,
+        template,
+    };
+    ownership::check_claim(
+        DocumentType::PropertyOwnership,
+        &input,
+        Some(&body.property_id),
+        body.wallet_address,
+        body.wallet_signature.as_deref(),
+    )
+    .await?;
+
+    let job = state
+        .jobs
+        .create(
+            DocumentType::PropertyOwnership,
+            body.proof_system,
+            body.wallet_address,
+            Some(body.property_id),
+            input,
+        )
+        .map_err(ApiError::Storage)?;
+    tracing::info!(job_id = %job.id, "job queued");
+    tokio::spawn(run_job(state, job.id.clone()));
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn prove_gst(
+    State(state): State<AppState>,
+    Upload {
+        pdf_bytes,
+        fields: body,
+    }: Upload<GstProofRequest>,
+) -> Result<(StatusCode, Json<Job>), ApiError> {
+    let template = state
+        .templates
+        .resolve(DocumentType::Gst, body.template.as_deref())
+        .map_err(ApiError::UnknownTemplate)?;
+    let input = JobInput {
+        pdf_bytes,
+        template,
+    };
+    ownership::check_claim(
+        DocumentType::Gst,
+        &input,
+        None,
+        body.wallet_address,
+        body.wallet_signature.as_deref(),
+    )
+    .await?;
+
+    let job = state
+        .jobs
+        .create(
+            DocumentType::Gst,
+            body.proof_system,
+            body.wallet_address,
+            None,
+            input,
+        )
+        .map_err(ApiError::Storage)?;
+    tracing::info!(job_id = %job.id, "job queued");
+    tokio::spawn(run_job(state, job.id.clone()));
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// The claim message of a property document, to sign with the owner's wallet before proving it.
+async fn commitment(
+    State(state): State<AppState>,
+    Upload {
+        pdf_bytes,
+        fields: body,
+    }: Upload<CommitmentRequest>,
+) -> Result<Json<CommitmentResponse>, ApiError> {
+    let template = state
+        .templates
+        .resolve(DocumentType::PropertyOwnership, body.template.as_deref())
+        .map_err(ApiError::UnknownTemplate)?;
+    let input = JobInput {
+        pdf_bytes,
+        template,
+    };
+    let document_commitment =
+        ownership::document_commitment(DocumentType::PropertyOwnership, &input).await?;
+
+    Ok(Json(CommitmentResponse {
+        document_type: DocumentType::PropertyOwnership.name(),
+        document_commitment,
+        message: ownership::claim_message(document_commitment, Some(&body.property_id)),
+    }))
+}
+
+/// The claim message of a GST certificate, to sign with the owner's wallet before proving it.
+async fn commitment_gst(
+    State(state): State<AppState>,
+    Upload {
+        pdf_bytes,
+        fields: body,
+    }: Upload<GstCommitmentRequest>,
+) -> Result<Json<CommitmentResponse>, ApiError> {
+    let template = state
+        .templates
+        .resolve(DocumentType::Gst, body.template.as_deref())
+        .map_err(ApiError::UnknownTemplate)?;
+    let input = JobInput {
+        pdf_bytes,
+        template,
+    };
+    let document_commitment = ownership::document_commitment(DocumentType::Gst, &input).await?;
+
+    Ok(Json(CommitmentResponse {
+        document_type: DocumentType::Gst.name(),
+        document_commitment,
+        message: ownership::claim_message(document_commitment, None),
+    }))
+}
+
+async fn execute(
+    State(state): State<AppState>,
+    Upload {
+        pdf_bytes,
+        fields: body,
+    }: Upload<ProofRequest>,
+) -> Result<Json<ExecuteResponse>, ApiError> {
+    let template = state
+        .templates
+        .resolve(DocumentType::PropertyOwnership, body.template.as_deref())
+        .map_err(ApiError::UnknownTemplate)?;
+    let claim = Claim {
+        claimant: body.wallet_address.into_array(),
+        property_id: body.property_id,
+    };
+    let input = JobInput {
+        pdf_bytes,
+        template,
+    };
+    execute_document(&state, DocumentType::PropertyOwnership, input, claim)
+        .await
+        .map(Json)
+}
+
+async fn execute_gst(
+    State(state): State<AppState>,
+    Upload {
+        pdf_bytes,
+        fields: body,
+    }: Upload<GstProofRequest>,
+) -> Result<Json<ExecuteResponse>, ApiError> {
+    let template = state
+        .templates
+        .resolve(DocumentType::Gst, body.template.as_deref())
+        .map_err(ApiError::UnknownTemplate)?;
+    let claim = Claim {
+        claimant: body.wallet_address.into_array(),
+        property_id: String::new(),
+    };
+    let input = JobInput {
+        pdf_bytes,
+        template,
+    };
+    execute_document(&state, DocumentType::Gst, input, claim)
+        .await
+        .map(Json)
+}
+
+/// Execute a document's program without proving it, so that the owner can check the extracted
+/// values before paying for a proof.
+async fn execute_document(
+    state: &AppState,
+    document_type: DocumentType,
+    input: JobInput,
+    claim: Claim,
+) -> Result<ExecuteResponse, ApiError> {
+    let state = state.clone();
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _span = span.enter();
+        let stdin = program_stdin(&input, &claim)?;
+        let program = state.program(document_type);
+
+        let (output, report) = match state.prover.execute(program.elf, &stdin) {
+            Ok(result) => result,
+            Err(e) => {
+                return Ok(ExecuteResponse {
+                    document_type: document_type.name(),
+                    public_values: None,
+                    cycles: 0,
+                    error: Some(
+                        ApiError::Prover(format!("Program execution failed: {}", e)).body(),
+                    ),
+                })
+            }
+        };
+
+        let public_values = output.as_slice();
+        Ok(ExecuteResponse {
+            document_type: document_type.name(),
+            public_values: Some(public_values_json(document_type, public_values)?),
+            cycles: report.total_instruction_count(),
+            error: check_error_code(committed_checks(document_type, public_values)?.error_code)
+                .err()
+                .map(|e| e.body()),
+        })
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("Execution task failed: {}", e)))?
+}
+
+/// Decode the public values committed by a document's program into JSON.
+fn public_values_json(
+    document_type: DocumentType,
+    public_values: &[u8],
+) -> Result<serde_json::Value, ApiError> {
+    let invalid = |e: alloy_sol_types::Error| invalid_public_values(&e);
+    let json = match document_type {
+        DocumentType::Gst => {
+            let decoded = GSTPublicValuesStruct::abi_decode(public_values).map_err(invalid)?;
+            serde_json::json!({
+                "gst_number": decoded.gst_number,
+                "legal_name": decoded.legal_name,
+                "signature_valid": decoded.signature_valid,
+                "document_commitment": decoded.document_commitment.to_string(),
+                "commitment_version": decoded.commitment_version,
+                "public_key_hash": decoded.public_key_hash.to_string(),
+                "issuer_root": decoded.issuer_root.to_string(),
+                "trusted_issuer": decoded.trusted_issuer,
+                "template_hash": decoded.template_hash.to_string(),
+                "claimant": decoded.claimant.to_string(),
+                "error_code": decoded.error_code,
+            })
+        }
+        DocumentType::PropertyOwnership => {
+            let decoded = PublicValuesStruct::abi_decode(public_values).map_err(invalid)?;
+            serde_json::json!({
+                "property_number": decoded.property_number,
+                "owner_name": decoded.owner_name,
+                "signature_valid": decoded.signature_valid,
+                "document_commitment": decoded.document_commitment.to_string(),
+                "commitment_version": decoded.commitment_version,
+                "property_id": decoded.property_id,
+                "public_key_hash": decoded.public_key_hash.to_string(),
+                "issuer_root": decoded.issuer_root.to_string(),
+                "trusted_issuer": decoded.trusted_issuer,
+                "template_hash": decoded.template_hash.to_string(),
+                "claimant": decoded.claimant.to_string(),
+                "error_code": decoded.error_code,
+            })
+        }
+    };
+    Ok(json)
+}
+
+/// The status updates that could not be delivered.
+async fn dead_webhooks(State(state): State<AppState>) -> Result<Json<Vec<Delivery>>, ApiError> {
+    state.outbox.dead().map(Json).map_err(ApiError::Storage)
+}
+
+/// Queue a dead-lettered status update again.
+async fn retry_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Delivery>, ApiError> {
+    state
+        .outbox
+        .retry(&id)
+        .map_err(ApiError::Storage)?
+        .map(Json)
+        .ok_or(ApiError::DeliveryNotFound(id))
+}
+
+async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Job>, ApiError> {
+    state
+        .jobs
+        .get(&id)
+        .map_err(ApiError::Storage)?
+        .map(Json)
+        .ok_or(ApiError::JobNotFound(id))
+}
+
+/// The proof generated by a job, once proving has finished.
+async fn get_job_proof(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SP1ProofWithPublicValues>, ApiError> {
+    if state.jobs.get(&id).map_err(ApiError::Storage)?.is_none() {
+        return Err(ApiError::JobNotFound(id));
+    }
+    state
+        .jobs
+        .proof(&id)
+        .map_err(ApiError::Storage)?
+        .map(Json)
+        .ok_or(ApiError::ProofNotFound(id))
+}
+
+/// Run a job from the stage it is in, so that a resumed job does not prove or submit twice:
+/// prove the document unless a proof is stored, store it on-chain unless a transaction is
+/// recorded, then report the outcome of property jobs to the status webhook.
+#[tracing::instrument(name = "job", skip_all, fields(job_id = %id))]
+async fn run_job(state: AppState, id: String) {
+    let jobs = &state.jobs;
+    let _worker = jobs.acquire_worker().await;
+
+    let job = match jobs.get(&id) {
+        Ok(Some(job)) => job,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to load job");
+            return;
+        }
+    };
+    tracing::info!(
+        document_type = job.document_type.name(),
+        proof_system = job.proof_system.as_str(),
+        stage = ?job.stage,
+        "running job"
+    );
+
+    if !job.stage.is_finished() {
+        match prove_and_store(&state, &job).await {
+            Ok(Some(transaction_hash)) => {
+                tracing::info!(%transaction_hash, "proof stored on-chain");
+                jobs.set_stage(&id, JobStage::Completed);
+            }
+            Ok(None) => {
+                tracing::info!("proof generated");
+                jobs.set_stage(&id, JobStage::Completed);
+            }
+            Err(e) => {
+                tracing::warn!(code = e.code(), error = %e, "job failed");
+                jobs.fail(&id, &e);
+            }
+        }
+    }
+
+    // Only proofs stored on-chain are reported to the status webhook
+    if job.document_type == DocumentType::PropertyOwnership && job.proof_system.is_evm() {
+        notify_property_job(&state, &id);
+    }
+}
+
+/// Prove the document of a job unless its proof is stored, then store the proof on-chain unless
+/// a transaction was already sent, in which case its receipt is awaited. A property document
+/// already verified on-chain for the same wallet and listing is neither proven nor submitted.
+/// Returns the transaction hash, or `None` for proofs that are not verified on-chain.
+async fn prove_and_store(state: &AppState, job: &Job) -> Result<Option<String>, ApiError> {
+    let jobs = &state.jobs;
+    if job.already_verified {
+        return Ok(job.transaction_hash.clone());
+    }
+    let proof = match jobs.proof(&job.id).map_err(ApiError::Storage)? {
+        Some(proof) => proof,
+        None => {
+            let input = jobs
+                .input(&job.id)
+                .map_err(ApiError::Storage)?
+                .ok_or_else(|| {
+                    ApiError::Storage("The document of the job is no longer stored".to_string())
+                })?;
+
+            // A property verified on-chain is not proven again
+            if job.document_type == DocumentType::PropertyOwnership && job.proof_system.is_evm() {
+                jobs.set_stage(&job.id, JobStage::Executing);
+                if let Some(verified) = find_verified_property(state, job, &input).await? {
+                    jobs.update(&job.id, |job| {
+                        job.already_verified = true;
+                        job.transaction_hash = Some(verified.transaction_hash.clone());
+                        job.block_number = verified.block_number;
+                        job.gas_used = verified.gas_used;
+                    });
+                    return Ok(Some(verified.transaction_hash));
+                }
+            }
+
+            let claim = Claim {
+                claimant: job.claimant.into_array(),
+                property_id: job.property_id.clone().unwrap_or_default(),
+            };
+            prove_document(state, job, input, claim).await?
+        }
+    };
+
+    if !job.proof_system.is_evm() {
+        return Ok(None);
+    }
+    let transaction_hash = match &job.transaction_hash {
+        Some(transaction_hash) if job.block_number.is_some() => {
+            return Ok(Some(transaction_hash.clone()))
+        }
+        // Sent before the server stopped: wait for the transaction instead of sending it again
+        Some(transaction_hash) => transaction_hash.parse::<TxHash>().map_err(|e| {
+            ApiError::Storage(format!(
+                "Invalid transaction hash {}: {}",
+                transaction_hash, e
+            ))
+        })?,
+        None => {
+            // Call the contract to verify and store the proof
+            jobs.set_stage(&job.id, JobStage::Submitting);
+            let transaction_hash = match job.document_type {
+                DocumentType::Gst => verify_and_store_gst(state, job, &proof).await,
+                DocumentType::PropertyOwnership => {
+                    verify_and_store_property(state, job, &proof).await
+                }
+            }?;
+            // Saved before waiting for the receipt, so that the proof is not sent twice if the
+            // server restarts in the meantime
+            jobs.update(&job.id, |job| {
+                job.transaction_hash = Some(format!("{:?}", transaction_hash))
+            });
+            transaction_hash
+        }
+    };
+
+    wait_for_receipt(state, job, transaction_hash)
+        .await
+        .map(Some)
+}
+
+/// Wait for a sent transaction to be mined, for at most [`RECEIPT_TIMEOUT`], and record its block
+/// and gas in the job. Returns the transaction hash.
+async fn wait_for_receipt(
+    state: &AppState,
+    job: &Job,
+    transaction_hash: TxHash,
+) -> Result<String, ApiError> {
+    let provider = state.verifier.provider().root();
+    let receipt = tokio::time::timeout(
+        RECEIPT_TIMEOUT,
+        PendingTransactionBuilder::new(provider, transaction_hash).get_receipt(),
+    )
+    .await
+    .map_err(|_| format!("not mined after {} seconds", RECEIPT_TIMEOUT.as_secs()))
+    .and_then(|receipt| receipt.map_err(|e| e.to_string()))
+    .map_err(|cause| ApiError::ReceiptUnavailable {
+        transaction_hash: format!("{:?}", transaction_hash),
+        cause,
+    })?;
+
+    let transaction_hash = format!("{:?}", transaction_hash);
+    tracing::info!(
+        %transaction_hash,
+        block_number = ?receipt.block_number,
+        gas_used = receipt.gas_used,
+        success = receipt.status(),
+        "transaction mined"
+    );
+    state.jobs.update(&job.id, |job| {
+        job.block_number = receipt.block_number;
+        job.gas_used = u64::try_from(receipt.gas_used).ok();
+    });
+    if !receipt.status() {
+        return Err(ApiError::TransactionReverted { transaction_hash });
+    }
+
+    Ok(transaction_hash)
+}
+
+/// Queue the outcome of a property job for the status webhook, if one is configured.
+fn notify_property_job(state: &AppState, id: &str) {
+    let Some(endpoint) = &state.config.webhooks.status_update_url else {
+        return;
+    };
+    let Ok(Some(job)) = state.jobs.get(id) else {
+        return;
+    };
+
+    let verifier = webhook::Verifier {
+        vkey: &state.program(job.document_type).vk.bytes32(),
+        chain_id: state.chain_id,
+        contract: *state.verifier.address(),
+    };
+    let version = state.config.webhooks.payload_version;
+    let body = match webhook::status_update(&job, version, &verifier) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to build status update");
+            return;
+        }
+    };
+
+    if let Err(e) = state
+        .outbox
+        .enqueue(id, webhook::STATUS_UPDATE_EVENT, endpoint, &body)
+    {
+        tracing::error!(error = %e, "failed to queue status update");
+    }
+}
+
+/// Execute the program to check the document, then prove it. The prover blocks, so both steps run
+/// on blocking threads.
+async fn prove_document(
+    state: &AppState,
+    job: &Job,
+    input: JobInput,
+    claim: Claim,
+) -> Result<SP1ProofWithPublicValues, ApiError> {
+    let stdin = program_stdin(&input, &claim)?;
+    let document_type = job.document_type;
+    let span = tracing::Span::current();
+
+    // Execute the program first so that a document the contract would reject is reported without
+    // proving it.
+    state.jobs.set_stage(&job.id, JobStage::Executing);
+    let public_values = {
+        let state = state.clone();
+        let stdin = stdin.clone();
+        let span = span.clone();
+        tokio::task::spawn_blocking(move || {
+            let _span = span.enter();
+            state
+                .prover
+                .execute(state.program(document_type).elf, &stdin)
+                .map(|(output, _)| output.to_vec())
+                .map_err(|e| ApiError::Prover(format!("Program execution failed: {}", e)))
+        })
+        .await
+        .map_err(|e| ApiError::Internal(format!("Execution task failed: {}", e)))??
+    };
+    check_executed(state, job, &public_values).await?;
+
+    let state = state.clone();
+    let id = job.id.clone();
+    let proof_system = job.proof_system;
+    tokio::task::spawn_blocking(move || {
+        let _span = span.enter();
+        let jobs = &state.jobs;
+        let program = state.program(document_type);
+
+        // The proof is saved as soon as it is generated, so that it is not lost if submitting it
+        // fails or the server restarts.
+        jobs.set_stage(&id, JobStage::Proving);
+        tracing::info!("proving");
+        let proof = state
+            .prover
+            .prove(&program.pk, &stdin, proof_system.mode())
+            .map_err(|e| ApiError::Prover(format!("Proof generation failed: {}", e)))?;
+        jobs.save_proof(&id, &proof);
+
+        Ok(proof)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("Proving task failed: {}", e)))?
+}
+
+async fn verify(
+    State(state): State<AppState>,
+    Json(proof): Json<SP1ProofWithPublicValues>,
+) -> Json<VerifyResponse> {
+    let vk = &state.program(DocumentType::PropertyOwnership).vk;
+
+    match state.prover.verify(&proof, vk) {
+        Ok(_) => Json(VerifyResponse {
+            valid: true,
+            error: None,
+        }),
+        Err(e) => Json(VerifyResponse {
+            valid: false,
+            error: Some(format!("Verification failed: {}", e)),
+        }),
+    }
+}
+
+/// The input of a document's program, in the order the programs read it.
+fn program_stdin(input: &JobInput, claim: &Claim) -> Result<SP1Stdin, ApiError> {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&input.pdf_bytes);
+    stdin.write(&input.template);
+    stdin.write(claim);
+    stdin.write(&issuer_membership(&input.pdf_bytes).map_err(ApiError::Internal)?);
+    Ok(stdin)
+}
+
+/// The public values of a document's program the contract checks besides the proof.
+struct CommittedChecks {
+    error_code: u8,
+    trusted_issuer: bool,
+    template_hash: B256,
+}
+
+/// Read the values the contract checks from the public values committed by a document's program.
+fn committed_checks(
+    document_type: DocumentType,
+    public_values: &[u8],
+) -> Result<CommittedChecks, ApiError> {
+    let checks = match document_type {
+        DocumentType::Gst => {
+            GSTPublicValuesStruct::abi_decode(public_values).map(|decoded| CommittedChecks {
+                error_code: decoded.error_code,
+                trusted_issuer: decoded.trusted_issuer,
+                template_hash: B256::from(decoded.template_hash.0),
+            })
+        }
+        DocumentType::PropertyOwnership => {
+            PublicValuesStruct::abi_decode(public_values).map(|decoded| CommittedChecks {
+                error_code: decoded.error_code,
+                trusted_issuer: decoded.trusted_issuer,
+                template_hash: B256::from(decoded.template_hash.0),
+            })
+        }
+    };
+    checks.map_err(|e| invalid_public_values(&e))
+}
+
+/// Check the public values committed by executing the program of a job, so that a document the
+/// contract would reject is not proven. The issuer and template are only checked for proofs
+/// verified on-chain.
+async fn check_executed(state: &AppState, job: &Job, public_values: &[u8]) -> Result<(), ApiError> {
+    let checks = committed_checks(job.document_type, public_values)?;
+    check_error_code(checks.error_code)?;
+    if !job.proof_system.is_evm() {
+        return Ok(());
+    }
+    check_trusted_issuer(checks.trusted_issuer)?;
+    check_template_approved(state, checks.template_hash).await
+}
+
+fn invalid_public_values(e: &alloy_sol_types::Error) -> ApiError {
+    ApiError::Prover(format!("Invalid public values: {}", e))
+}
+
+/// Report why the program rejected a document, from the error code in its public values.
+fn check_error_code(error_code: u8) -> Result<(), ApiError> {
+    if error_code != 0 {
+        return Err(ApiError::DocumentRejected { error_code });
+    }
+    Ok(())
+}
+
+/// Load the extraction templates from the templates directory.
+async fn index() -> Html<&'static str> {
+    Html(include_str!("../../../index.html"))
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    logging::init();
+
+    let config = Config::load().expect("invalid configuration");
+    let cors = config.cors().expect("invalid CORS origins");
+    let max_upload_bytes = config.limits.max_upload_bytes;
+    let port = config.port;
+    let admin_token = config.admin_token().map(Arc::<str>::from);
+    if admin_token.is_none() {
+        tracing::warn!(
+            token_env = %config.admin.token_env,
+            "admin token not set; the admin endpoints are disabled"
+        );
+    }
+
+    let store = Arc::new(JobStore::open(&config.jobs_db).expect("failed to open the job database"));
+    let jobs = JobQueue::new(store.clone(), config.limits.max_concurrent_jobs);
+    let outbox = WebhookOutbox::new(
+        store,
+        jobs.clone(),
+        config.webhook_secret(),
+        config.webhooks.max_attempts,
+    );
+
+    // Set up the programs once; every job shares their keys
+    let state = AppState::new(config, jobs.clone(), outbox.clone())
+        .await
+        .expect("failed to set up the application state");
+
+    // Resume the jobs interrupted by the last shutdown
+    let notify = state.config.webhooks.status_update_url.is_some();
+    let resumable = jobs
+        .resumable(notify)
+        .expect("failed to load unfinished jobs");
+    if !resumable.is_empty() {
+        tracing::info!(jobs = resumable.len(), "resuming unfinished jobs");
+    }
+    for id in resumable {
+        tokio::spawn(run_job(state.clone(), id));
+    }
+    outbox.spawn();
+
+    let admin = Router::new()
+        .route("/webhooks/dead", get(dead_webhooks))
+        .route("/webhooks/:id/retry", post(retry_webhook))
+        .route_layer(middleware::from_fn_with_state(
+            admin_token,
+            admin::require_token,
+        ));
+
+    let app = Router::new()
+        .route("/", get(index))
+        .route("/prove", post(prove))
+        .route("/prove/gst", post(prove_gst))
+        .route("/commitment", post(commitment))
+        .route("/commitment/gst", post(commitment_gst))
+        .route("/execute", post(execute))
+        .route("/execute/gst", post(execute_gst))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/proof", get(get_job_proof))
+        .route("/verify", post(verify))
+        .merge(admin)
+        .layer(DefaultBodyLimit::max(max_upload_bytes))
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(cors)
+        .with_state(state);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    tracing::info!("listening on {}", addr);
+
+    let listener = TcpListener::bind(addr).await.unwrap();
+    serve(listener, app.into_make_service()).await.unwrap();
+}
+
+/// The span of a request, tagged with a fresh request id.
+fn request_span(request: &Request) -> tracing::Span {
+    tracing::info_span!(
+        "request",
+        request_id = %uuid::Uuid::new_v4(),
+        method = %request.method(),
+        path = %request.uri().path(),
+    )
+}
+
+/// Check that the claimant committed in the proof is the wallet the proof is submitted for.
+///
+/// The server relays transactions with its own signer, so the contract cannot compare the
+/// claimant with `msg.sender`; the check is made here before submitting instead.
+fn check_claimant(committed: &[u8], expected: Address) -> Result<(), ApiError> {
+    if committed != expected.as_slice() {
+        return Err(ApiError::ProofMismatch(format!(
+            "Proof is bound to claimant 0x{}, expected {}",
+            hex::encode(committed),
+            expected
+        )));
+    }
+    Ok(())
+}
+
+/// Check that the document was signed by a key in the trusted issuer registry. The contract
+/// rejects other documents, so there is no point in submitting them.
+fn check_trusted_issuer(trusted_issuer: bool) -> Result<(), ApiError> {
+    if !trusted_issuer {
+        return Err(ApiError::UntrustedIssuer);
+    }
+    Ok(())
+}
+
+/// Check that the contract accepts proofs extracted with a template. The check only saves proving
+/// costs, as the contract checks the template again, so a failing node does not fail the job.
+async fn check_template_approved(state: &AppState, template_hash: B256) -> Result<(), ApiError> {
+    match state.verifier.approvedTemplates(template_hash).call().await {
+        Ok(approved) if !approved._0 => Err(ApiError::TemplateNotApproved {
+            template_hash: template_hash.to_string(),
+        }),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to check whether the template is approved");
+            Ok(())
+        }
+    }
+}
+
+/// Simulate storing the proof of a job and record the gas estimate of the transaction. Returns the
+/// gas limit to send the transaction with.
+async fn simulate(
+    state: &AppState,
+    job: &Job,
+    proof: &SP1ProofWithPublicValues,
+) -> Result<u64, ApiError> {
+    let gas_estimate = preflight::simulate(&state.verifier, job.document_type, proof).await?;
+    let gas_limit = preflight::gas_limit(gas_estimate);
+    tracing::info!(gas_estimate, gas_limit, "transaction simulated");
+    state
+        .jobs
+        .update(&job.id, |job| job.gas_estimate = Some(gas_estimate));
+    Ok(gas_limit)
+}
+
+/// Send the transaction verifying and storing the property proof of a job, once simulating the
+/// call shows that it would succeed. Returns the transaction hash.
+async fn verify_and_store_property(
+    state: &AppState,
+    job: &Job,
+    proof: &SP1ProofWithPublicValues,
+) -> Result<TxHash, ApiError> {
+    let contract = &state.verifier;
+    let vk = &state.program(job.document_type).vk;
+    let property_id = job.property_id.as_deref().unwrap_or_default();
+
+    // Deserialize the public values.
+    let bytes = proof.public_values.as_slice();
+    let decoded = PublicValuesStruct::abi_decode(bytes).map_err(|e| invalid_public_values(&e))?;
+    check_claimant(decoded.claimant.as_slice(), job.claimant)?;
+    check_trusted_issuer(decoded.trusted_issuer)?;
+    if decoded.property_id != property_id {
+        return Err(ApiError::ProofMismatch(format!(
+            "Proof is bound to property {}, expected {}",
+            decoded.property_id, property_id
+        )));
+    }
+
+    tracing::info!(
+        vkey = %vk.bytes32(),
+        property_id = %decoded.property_id,
+        property_number = %pii(&decoded.property_number),
+        owner_name = %pii(&decoded.owner_name),
+        signature_valid = decoded.signature_valid,
+        document_commitment = %decoded.document_commitment,
+        public_key_hash = %decoded.public_key_hash,
+        "submitting property proof"
+    );
+    let gas_limit = simulate(state, job, proof).await?;
+
+    // Prepare the call data
+    let public_values = Bytes::from(bytes.to_vec());
+    let proof_bytes = Bytes::from(proof.bytes().to_vec());
+
+    // Call the contract function
+    let call = contract.verifyAndStoreProperty(public_values, proof_bytes);
+
+    // Execute the transaction
+    let pending_tx = call
+        .gas(gas_limit)
+        .send()
+        .await
+        .map_err(|e| preflight::call_error("verifyAndStoreProperty", e))?;
+
+    let transaction_hash = *pending_tx.tx_hash();
+    tracing::info!(contract = %contract.address(), %transaction_hash, "transaction sent");
+    Ok(transaction_hash)
+}
+
+/// Send the transaction verifying and storing the GST proof of a job, once simulating the call
+/// shows that it would succeed. Returns the transaction hash.
+async fn verify_and_store_gst(
+    state: &AppState,
+    job: &Job,
+    proof: &SP1ProofWithPublicValues,
+) -> Result<TxHash, ApiError> {
+    let contract = &state.verifier;
+    let vk = &state.program(job.document_type).vk;
+
+    // Deserialize the public values.
+    let bytes = proof.public_values.as_slice();
+    let decoded =
+        GSTPublicValuesStruct::abi_decode(bytes).map_err(|e| invalid_public_values(&e))?;
+    check_claimant(decoded.claimant.as_slice(), job.claimant)?;
+    check_trusted_issuer(decoded.trusted_issuer)?;
+
+    tracing::info!(
+        vkey = %vk.bytes32(),
+        gst_number = %pii(&decoded.gst_number),
+        legal_name = %pii(&decoded.legal_name),
+        signature_valid = decoded.signature_valid,
+        document_commitment = %decoded.document_commitment,
+        public_key_hash = %decoded.public_key_hash,
+        "submitting GST proof"
+    );
+    let gas_limit = simulate(state, job, proof).await?;
+
+    // Prepare the call data
+    let public_values = Bytes::from(bytes.to_vec());
+    let proof_bytes = Bytes::from(proof.bytes().to_vec());
+
+    let pending_tx = contract
+        .verifyAndStoreGST(public_values, proof_bytes)
+        .gas(gas_limit)
+        .send()
+        .await
+        .map_err(|e| preflight::call_error("verifyAndStoreGST", e))?;
+
+    let transaction_hash = *pending_tx.tx_hash();
+    tracing::info!(contract = %contract.address(), %transaction_hash, "transaction sent");
+    Ok(transaction_hash)
+}
@@ -0,0 +1,340 @@
+//! Webhook outbox
+//!
+//! Status updates are saved to the job database before they are sent, and a background task
+//! delivers them, retrying failed attempts with exponential backoff, so that an update survives an
+//! unreachable endpoint or a restart of the server. A delivery that still fails after
+//! `max_attempts` attempts is dead-lettered: `GET /webhooks/dead` lists it and
+//! `POST /webhooks/:id/retry` queues it again. A client error other than `408 Request Timeout` and
+//! `429 Too Many Requests` means the endpoint rejected the update and would reject it again, so
+//! the delivery is dead-lettered at once.
+//!
+//! Every attempt is signed so that the receiver can authenticate the server:
+//!
+//! - `X-Webhook-Id`: the idempotency key of the delivery, the same for every attempt,
+//! - `X-Webhook-Timestamp`: the Unix time of the attempt, in seconds,
+//! - `X-Webhook-Signature`: `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>`, keyed with
+//!   the webhook secret.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::Notify;
+
+use crate::error::ApiError;
+use crate::jobs::{unix_time, JobQueue, JobStage};
+use crate::store::JobStore;
+
+/// Default number of attempts before a delivery is dead-lettered.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 10;
+
+/// Header holding the idempotency key of a delivery.
+pub const ID_HEADER: &str = "X-Webhook-Id";
+/// Header holding the time the attempt was signed at.
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+/// Header holding the signature of the attempt.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Delay before the first retry, doubled after every failed attempt.
+const BASE_RETRY_DELAY_SECS: u64 = 10;
+/// Longest delay between two attempts.
+const MAX_RETRY_DELAY_SECS: u64 = 60 * 60;
+/// How often due deliveries are looked for when no update is queued.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Timeout of a delivery attempt.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The state of a delivery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryState {
+    /// Waiting for its next attempt.
+    Pending,
+    Delivered,
+    /// Failed `max_attempts` times; only retried on request.
+    Dead,
+}
+
+impl DeliveryState {
+    const ALL: [DeliveryState; 3] = [
+        DeliveryState::Pending,
+        DeliveryState::Delivered,
+        DeliveryState::Dead,
+    ];
+
+    /// The name of the state, as stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryState::Pending => "pending",
+            DeliveryState::Delivered => "delivered",
+            DeliveryState::Dead => "dead",
+        }
+    }
+
+    /// Look up a state by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.as_str() == name)
+    }
+}
+
+/// A status update and its delivery attempts. The endpoint and the body, which holds personal
+/// data, are not reported by the API.
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    /// Idempotency key, derived from the job and the event so that an update is queued once.
+    pub id: String,
+    pub job_id: String,
+    #[serde(skip)]
+    pub url: String,
+    #[serde(skip)]
+    pub body: String,
+    pub state: DeliveryState,
+    pub attempts: u32,
+    /// Unix time of the next attempt of a pending delivery, in seconds.
+    pub next_attempt_at: u64,
+    /// Why the last attempt failed.
+    pub last_error: Option<String>,
+    /// Unix timestamps, in seconds.
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// A failed delivery attempt.
+#[derive(Debug)]
+struct AttemptError {
+    message: String,
+    /// Whether the endpoint rejected the update, which retrying would not change.
+    permanent: bool,
+}
+
+impl AttemptError {
+    fn transient(message: String) -> Self {
+        Self {
+            message,
+            permanent: false,
+        }
+    }
+}
+
+/// The queue of status updates and the task delivering them.
+#[derive(Clone)]
+pub struct WebhookOutbox {
+    store: Arc<JobStore>,
+    jobs: JobQueue,
+    client: reqwest::Client,
+    /// Key of the signatures. Attempts fail while it is not set.
+    secret: Option<Arc<Vec<u8>>>,
+    max_attempts: u32,
+    queued: Arc<Notify>,
+}
+
+impl WebhookOutbox {
+    /// An outbox saving deliveries to `store`, signing them with `secret` and dead-lettering them
+    /// after `max_attempts` failed attempts.
+    pub fn new(
+        store: Arc<JobStore>,
+        jobs: JobQueue,
+        secret: Option<Vec<u8>>,
+        max_attempts: u32,
+    ) -> Self {
+        Self {
+            store,
+            jobs,
+            client: reqwest::Client::new(),
+            secret: secret.map(Arc::new),
+            max_attempts: max_attempts.max(1),
+            queued: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Queue the `event` update of a job for `url`. An update already queued for the same job and
+    /// event is not queued again.
+    pub fn enqueue(
+        &self,
+        job_id: &str,
+        event: &str,
+        url: &str,
+        body: &impl Serialize,
+    ) -> Result<(), String> {
+        let now = unix_time();
+        let delivery = Delivery {
+            id: format!("{}:{}", job_id, event),
+            job_id: job_id.to_string(),
+            url: url.to_string(),
+            body: serde_json::to_string(body).map_err(|e| e.to_string())?,
+            state: DeliveryState::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        if self.store.insert_delivery(&delivery)? {
+            tracing::info!(delivery_id = %delivery.id, "status update queued");
+            self.queued.notify_one();
+        }
+        Ok(())
+    }
+
+    /// The dead-lettered deliveries, oldest first.
+    pub fn dead(&self) -> Result<Vec<Delivery>, String> {
+        self.store.deliveries(DeliveryState::Dead)
+    }
+
+    /// Queue a delivery again for `max_attempts` attempts. Returns `None` for an unknown id.
+    pub fn retry(&self, id: &str) -> Result<Option<Delivery>, String> {
+        let Some(mut delivery) = self.store.delivery(id)? else {
+            return Ok(None);
+        };
+        if delivery.state != DeliveryState::Delivered {
+            let now = unix_time();
+            delivery.state = DeliveryState::Pending;
+            delivery.attempts = 0;
+            delivery.next_attempt_at = now;
+            delivery.updated_at = now;
+            self.store.save_delivery(&delivery)?;
+            self.queued.notify_one();
+        }
+        Ok(Some(delivery))
+    }
+
+    /// Deliver queued updates in the background until the server stops.
+    pub fn spawn(&self) {
+        let outbox = self.clone();
+        tokio::spawn(async move {
+            loop {
+                outbox.deliver_due().await;
+                let _ = tokio::time::timeout(POLL_INTERVAL, outbox.queued.notified()).await;
+            }
+        });
+    }
+
+    async fn deliver_due(&self) {
+        let due = match self.store.due_deliveries(unix_time()) {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::error!(error = %e, "failed to load queued status updates");
+                return;
+            }
+        };
+        for delivery in due {
+            self.attempt(delivery).await;
+        }
+    }
+
+    /// Make one attempt and schedule the next one if it fails.
+    #[tracing::instrument(
+        name = "webhook",
+        skip_all,
+        fields(delivery_id = %delivery.id, job_id = %delivery.job_id)
+    )]
+    async fn attempt(&self, mut delivery: Delivery) {
+        let result = self.send(&delivery).await;
+        let now = unix_time();
+        delivery.attempts += 1;
+        delivery.updated_at = now;
+        if let Err(e) = &result {
+            let error = ApiError::Webhook(e.message.clone()).body();
+            self.jobs
+                .update(&delivery.job_id, |job| job.webhook_error = Some(error));
+        }
+        match result {
+            Ok(()) => {
+                tracing::info!(attempts = delivery.attempts, "status update delivered");
+                delivery.state = DeliveryState::Delivered;
+                delivery.last_error = None;
+                self.jobs.update(&delivery.job_id, |job| {
+                    job.webhook_delivered = true;
+                    job.webhook_error = None;
+                    if job.stage == JobStage::Completed {
+                        job.stage = JobStage::Notified;
+                    }
+                });
+            }
+            Err(e) if e.permanent || delivery.attempts >= self.max_attempts => {
+                tracing::error!(
+                    attempts = delivery.attempts,
+                    permanent = e.permanent,
+                    error = %e.message,
+                    "status update dead-lettered"
+                );
+                delivery.state = DeliveryState::Dead;
+                delivery.last_error = Some(e.message);
+            }
+            Err(e) => {
+                delivery.next_attempt_at = now + retry_delay(delivery.attempts);
+                tracing::warn!(
+                    attempts = delivery.attempts,
+                    next_attempt_at = delivery.next_attempt_at,
+                    error = %e.message,
+                    "status update failed"
+                );
+                delivery.last_error = Some(e.message);
+            }
+        }
+        if let Err(e) = self.store.save_delivery(&delivery) {
+            tracing::error!(error = %e, "failed to save status update");
+        }
+    }
+
+    async fn send(&self, delivery: &Delivery) -> Result<(), AttemptError> {
+        let secret = self
+            .secret
+            .as_ref()
+            .ok_or_else(|| AttemptError::transient("The webhook secret is not set".to_string()))?;
+        let timestamp = unix_time();
+
+        let response = self
+            .client
+            .post(&delivery.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(ID_HEADER, &delivery.id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                signature(secret, timestamp, &delivery.body),
+            )
+            .body(delivery.body.clone())
+            .send()
+            .await
+            // The endpoint URL may hold a token
+            .map_err(|e| AttemptError::transient(e.without_url().to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AttemptError {
+                message: format!("endpoint responded with status {}", status),
+                permanent: is_permanent_failure(status),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The signature of a delivery attempt made at `timestamp`.
+pub fn signature(secret: &[u8], timestamp: u64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Whether a response status means the endpoint rejected the update for good. Timeouts and rate
+/// limits are retried like server errors.
+pub fn is_permanent_failure(status: StatusCode) -> bool {
+    status.is_client_error()
+        && status != StatusCode::REQUEST_TIMEOUT
+        && status != StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Delay before the attempt following `attempts` failed ones.
+pub fn retry_delay(attempts: u32) -> u64 {
+    BASE_RETRY_DELAY_SECS
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(MAX_RETRY_DELAY_SECS)
+}
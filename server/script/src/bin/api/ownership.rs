@@ -0,0 +1,102 @@
+//! Wallet ownership
+//!
+//! A proof binds a document to the `wallet_address` of the request, and the contract records the
+//! first wallet that stores it as the document's claimant. Anyone holding a copy of a document
+//! could otherwise claim it for any wallet, so a job is only queued once the wallet has signed the
+//! document:
+//!
+//! 1. `POST /commitment` (or `/commitment/gst`) with the document returns its commitment, computed
+//!    natively in milliseconds, and the message to sign, naming the commitment and the listing,
+//! 2. the owner signs the message with `personal_sign` (EIP-191),
+//! 3. `POST /prove` (or `/prove/gst`) is sent with the signature in `wallet_signature`. The server
+//!    computes the commitment again, recovers the signer of the message and rejects the request
+//!    with `invalid_wallet_signature` unless it is `wallet_address`.
+
+use alloy::primitives::{Address, PrimitiveSignature, B256};
+use zkpdf_template_lib::{DocumentType, DocumentVerifier, GstVerifier, PropertyOwnershipVerifier};
+
+use crate::error::ApiError;
+use crate::jobs::JobInput;
+
+/// Compute the commitment of a document by verifying it outside the zkVM. A document the program
+/// would reject fails with `document_rejected`.
+pub async fn document_commitment(
+    document_type: DocumentType,
+    input: &JobInput,
+) -> Result<B256, ApiError> {
+    let pdf_bytes = input.pdf_bytes.clone();
+    let template = input.template.clone();
+    let commitment = tokio::task::spawn_blocking(move || match document_type {
+        DocumentType::Gst => GstVerifier::verify(pdf_bytes, &template)
+            .map(|certificate| GstVerifier::commitment(&certificate)),
+        DocumentType::PropertyOwnership => PropertyOwnershipVerifier::verify(pdf_bytes, &template)
+            .map(|certificate| PropertyOwnershipVerifier::commitment(&certificate)),
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("Verification task failed: {}", e)))?;
+
+    commitment
+        .map(B256::from)
+        .map_err(|e| ApiError::DocumentRejected {
+            error_code: e.code(),
+        })
+}
+
+/// The message a wallet signs to claim a document, for a listing in the case of property
+/// documents.
+pub fn claim_message(document_commitment: B256, property_id: Option<&str>) -> String {
+    match property_id {
+        Some(property_id) => format!(
+            "Prove ownership of document {} for listing {}",
+            document_commitment, property_id
+        ),
+        None => format!("Prove ownership of document {}", document_commitment),
+    }
+}
+
+/// Check that `signature` is the signature of `message` by `wallet`.
+pub fn check_wallet_signature(
+    message: &str,
+    signature: Option<&str>,
+    wallet: Address,
+) -> Result<(), ApiError> {
+    let signature = signature.ok_or_else(|| {
+        ApiError::InvalidWalletSignature(
+            "Missing `wallet_signature`: sign the message returned by `POST /commitment` with \
+             the wallet"
+                .to_string(),
+        )
+    })?;
+    let signer = signature
+        .parse::<PrimitiveSignature>()
+        .and_then(|signature| signature.recover_address_from_msg(message))
+        .map_err(|e| {
+            ApiError::InvalidWalletSignature(format!(
+                "`wallet_signature` is not a valid signature: {}",
+                e
+            ))
+        })?;
+    if signer != wallet {
+        return Err(ApiError::InvalidWalletSignature(format!(
+            "The document is signed by {}, not by wallet {}",
+            signer, wallet
+        )));
+    }
+    Ok(())
+}
+
+/// Check that the wallet of a request signed the claim message of its document.
+pub async fn check_claim(
+    document_type: DocumentType,
+    input: &JobInput,
+    property_id: Option<&str>,
+    wallet: Address,
+    signature: Option<&str>,
+) -> Result<(), ApiError> {
+    let document_commitment = document_commitment(document_type, input).await?;
+    check_wallet_signature(
+        &claim_message(document_commitment, property_id),
+        signature,
+        wallet,
+    )
+}
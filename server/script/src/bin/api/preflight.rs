@@ -0,0 +1,87 @@
+//! Transaction pre-flight
+//!
+//! A proof the contract rejects, e.g. because the SP1 verifier expects another selector or
+//! program verification key, only shows up as a reverted transaction once it has been paid for.
+//! Before sending the transaction storing a proof, the server therefore simulates it:
+//!
+//! 1. `verifyPropertyProof` or `verifyGSTProof` is called with `eth_call`, which runs the SP1
+//!    verifier and the template and issuer checks without storing anything,
+//! 2. the gas of `verifyAndStoreProperty` or `verifyAndStoreGST` is estimated, which also runs the
+//!    claimant and listing checks of the stored record.
+//!
+//! A revert in either step fails the job with `call_reverted` and the decoded reason, and nothing
+//! is sent. The gas estimate is recorded in the job, and the transaction is sent with the estimate
+//! and a margin of `GAS_LIMIT_MARGIN_PERCENT` as its gas limit, since the state the estimate ran
+//! against may change before the transaction is mined.
+
+use alloy::primitives::Bytes;
+use sp1_sdk::SP1ProofWithPublicValues;
+use zkpdf_template_lib::DocumentType;
+use zkpdf_template_script::contract::describe_revert;
+
+use crate::error::ApiError;
+use crate::state::CertificateVerifier;
+
+/// Gas added to the estimate to set the gas limit of a transaction, in percent of the estimate.
+pub const GAS_LIMIT_MARGIN_PERCENT: u64 = 20;
+
+/// Simulate storing a proof. Returns the gas estimate of the transaction.
+pub async fn simulate(
+    contract: &CertificateVerifier,
+    document_type: DocumentType,
+    proof: &SP1ProofWithPublicValues,
+) -> Result<u64, ApiError> {
+    let public_values = Bytes::from(proof.public_values.to_vec());
+    let proof_bytes = Bytes::from(proof.bytes().to_vec());
+
+    let gas_estimate = match document_type {
+        DocumentType::Gst => {
+            contract
+                .verifyGSTProof(public_values.clone(), proof_bytes.clone())
+                .call()
+                .await
+                .map_err(|e| call_error("verifyGSTProof", e))?;
+            contract
+                .verifyAndStoreGST(public_values, proof_bytes)
+                .estimate_gas()
+                .await
+                .map_err(|e| call_error("verifyAndStoreGST", e))?
+        }
+        DocumentType::PropertyOwnership => {
+            contract
+                .verifyPropertyProof(public_values.clone(), proof_bytes.clone())
+                .call()
+                .await
+                .map_err(|e| call_error("verifyPropertyProof", e))?;
+            contract
+                .verifyAndStoreProperty(public_values, proof_bytes)
+                .estimate_gas()
+                .await
+                .map_err(|e| call_error("verifyAndStoreProperty", e))?
+        }
+    };
+    Ok(gas_estimate)
+}
+
+/// The gas limit of a transaction estimated to use `gas_estimate`.
+pub fn gas_limit(gas_estimate: u64) -> u64 {
+    gas_estimate.saturating_add(gas_estimate.saturating_mul(GAS_LIMIT_MARGIN_PERCENT) / 100)
+}
+
+/// Map the failure of a contract call: a revert is decoded, anything else means the node could
+/// not be reached or rejected the request.
+pub fn call_error(call: &str, error: alloy::contract::Error) -> ApiError {
+    let revert_data = match &error {
+        alloy::contract::Error::TransportError(e) => e
+            .as_error_resp()
+            .and_then(|payload| payload.as_revert_data()),
+        _ => None,
+    };
+    match revert_data {
+        Some(data) => ApiError::CallReverted {
+            call: call.to_string(),
+            reason: describe_revert(&data),
+        },
+        None => ApiError::TransactionSendFailed(error.to_string()),
+    }
+}
@@ -0,0 +1,143 @@
+//! Transaction signer
+//!
+//! The key sending the verification transactions comes from one of:
+//!
+//! - a hex private key in the environment variable named by `private_key_env` (default
+//!   `PRIVATE_KEY`),
+//! - an encrypted JSON keystore (`keystore`) and the file holding its password (`password_file`),
+//! - a BIP-39 mnemonic in the environment variable named by `mnemonic_env`, and a
+//!   `derivation_path`.
+//!
+//! There is no default key, except on a local anvil node where anvil's first development key is
+//! used when none is configured. That key is public, so it is refused on any other chain.
+
+use alloy::{
+    primitives::{address, Address},
+    signers::local::{coins_bip39::English, MnemonicBuilder, PrivateKeySigner},
+};
+
+use crate::config::SignerConfig;
+use crate::state::ANVIL_CHAIN_ID;
+
+/// Default derivation path of the key in a mnemonic, the first account of most wallets.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// First development key of anvil, funded on every anvil node.
+const ANVIL_DEV_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// Address of [`ANVIL_DEV_KEY`].
+const ANVIL_DEV_ADDRESS: Address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+
+/// Where the signer's key is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerSource {
+    /// A hex private key in an environment variable.
+    PrivateKey { env: String },
+    /// An encrypted JSON keystore and the file holding its password.
+    Keystore { path: String, password_file: String },
+    /// A mnemonic in an environment variable and the derivation path of the key.
+    Mnemonic {
+        env: String,
+        derivation_path: String,
+    },
+}
+
+impl SignerSource {
+    /// The configured key source, or `None` if no key is configured.
+    pub fn from_config(config: &SignerConfig) -> Result<Option<Self>, String> {
+        match (
+            &config.keystore,
+            &config.password_file,
+            &config.mnemonic_env,
+        ) {
+            (Some(_), _, Some(_)) => {
+                Err("Configure either a signer keystore or a signer mnemonic, not both".to_string())
+            }
+            (Some(path), Some(password_file), None) => Ok(Some(SignerSource::Keystore {
+                path: path.clone(),
+                password_file: password_file.clone(),
+            })),
+            (Some(_), None, None) => {
+                Err("The signer keystore needs a password file (`password_file`)".to_string())
+            }
+            (None, Some(_), _) => {
+                Err("A signer password file is set without a keystore".to_string())
+            }
+            (None, None, Some(env)) => {
+                if std::env::var(env).is_err() {
+                    return Err(format!(
+                        "The signer mnemonic is not set: {} is missing",
+                        env
+                    ));
+                }
+                Ok(Some(SignerSource::Mnemonic {
+                    env: env.clone(),
+                    derivation_path: config.derivation_path.clone(),
+                }))
+            }
+            (None, None, None) => Ok(std::env::var(&config.private_key_env).is_ok().then(|| {
+                SignerSource::PrivateKey {
+                    env: config.private_key_env.clone(),
+                }
+            })),
+        }
+    }
+
+    /// Read the key.
+    pub fn load(&self) -> Result<PrivateKeySigner, String> {
+        match self {
+            SignerSource::PrivateKey { env } => {
+                let private_key = std::env::var(env).map_err(|_| format!("{} is not set", env))?;
+                private_key
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("Invalid private key in {}: {}", env, e))
+            }
+            SignerSource::Keystore {
+                path,
+                password_file,
+            } => {
+                let password = std::fs::read_to_string(password_file).map_err(|e| {
+                    format!("Failed to read the password file {}: {}", password_file, e)
+                })?;
+                PrivateKeySigner::decrypt_keystore(path, password.trim_end_matches(['\r', '\n']))
+                    .map_err(|e| format!("Failed to decrypt the keystore {}: {}", path, e))
+            }
+            SignerSource::Mnemonic {
+                env,
+                derivation_path,
+            } => {
+                let phrase = std::env::var(env).map_err(|_| format!("{} is not set", env))?;
+                MnemonicBuilder::<English>::default()
+                    .phrase(phrase.trim())
+                    .derivation_path(derivation_path)
+                    .and_then(|builder| builder.build())
+                    .map_err(|e| format!("Invalid mnemonic in {}: {}", env, e))
+            }
+        }
+    }
+}
+
+/// Load the configured signer for `chain_id`, applying the default key policy.
+pub fn load_signer(config: &SignerConfig, chain_id: u64) -> Result<PrivateKeySigner, String> {
+    let signer = match SignerSource::from_config(config)? {
+        Some(source) => source.load()?,
+        None if chain_id == ANVIL_CHAIN_ID => {
+            tracing::warn!("no signer configured, using anvil's first development key");
+            ANVIL_DEV_KEY.parse().expect("valid anvil key")
+        }
+        None => {
+            return Err(format!(
+                "No signer configured for chain {}: set {}, a keystore or a mnemonic",
+                chain_id, config.private_key_env
+            ))
+        }
+    };
+    if signer.address() == ANVIL_DEV_ADDRESS && chain_id != ANVIL_CHAIN_ID {
+        return Err(format!(
+            "Refusing to sign with anvil's public development key on chain {}",
+            chain_id
+        ));
+    }
+    Ok(signer)
+}
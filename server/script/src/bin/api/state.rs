@@ -0,0 +1,252 @@
+//! Shared application state
+//!
+//! Setting up a program derives its proving key, which takes seconds, so the prover client, the
+//! keys of every registered program, the extraction templates and the certificate verifier
+//! contract are built once at startup and shared by the handlers through axum `State`.
+//!
+//! The prover backend is selected with the `prover` setting: `network` proves on the Succinct
+//! prover network, `cpu` proves locally, and `mock` generates mock proofs instantly. Mock proofs
+//! are only accepted by `SP1MockVerifier`, so in mock mode the server must submit to a certificate
+//! verifier deployed with the mock verifier on a local anvil node.
+//!
+//! The certificate verifier address and the block it was deployed in are taken from the
+//! deployment record of the chain the RPC node reports, unless `contract_address` is set. The
+//! server refuses to start if the contract was deployed with other program verification keys than
+//! the local ELFs, since every proof would then be rejected on-chain after minutes of proving.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use alloy::{
+    network::EthereumWallet,
+    primitives::utils::format_ether,
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    transports::http::{Client, Http},
+};
+use sp1_prover::components::CpuProverComponents;
+use sp1_sdk::{HashableKey, Prover, ProverClient, SP1ProvingKey, SP1VerifyingKey};
+use zkpdf_template_lib::DocumentType;
+use zkpdf_template_script::{
+    contract::CertificateVerifierInstance,
+    logging::{redact_url, redact_url_in},
+    program_elf,
+    templates::TemplateRegistry,
+};
+
+use crate::config::{Config, ProverMode};
+use crate::deployments::{certificate_verifier_address, certificate_verifier_block};
+use crate::jobs::JobQueue;
+use crate::outbox::WebhookOutbox;
+use crate::signer::load_signer;
+
+/// Chain id of a local anvil node, the only chain mock proofs are submitted to.
+pub const ANVIL_CHAIN_ID: u64 = 31337;
+
+/// The prover backend selected by the `prover` setting.
+pub type ProverBackend = dyn Prover<CpuProverComponents>;
+
+/// The certificate verifier contract, called with the configured signer.
+pub type CertificateVerifier =
+    CertificateVerifierInstance<Http<Client>, Arc<dyn Provider<Http<Client>>>>;
+
+/// A registered program and its keys.
+pub struct Program {
+    pub elf: &'static [u8],
+    pub pk: SP1ProvingKey,
+    pub vk: SP1VerifyingKey,
+}
+
+/// State shared by the handlers and the job workers.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub jobs: JobQueue,
+    pub outbox: WebhookOutbox,
+    pub prover: Arc<ProverBackend>,
+    programs: Arc<HashMap<DocumentType, Program>>,
+    pub templates: Arc<TemplateRegistry>,
+    pub verifier: Arc<CertificateVerifier>,
+    /// Id of the chain the contract is deployed on.
+    pub chain_id: u64,
+    /// Block the contract was deployed in, where the search for its events starts.
+    pub deployment_block: Option<u64>,
+}
+
+impl AppState {
+    /// Load the extraction templates, set up every registered program, connect to the certificate
+    /// verifier contract and check that it accepts the proofs of these programs.
+    pub async fn new(
+        config: Config,
+        jobs: JobQueue,
+        outbox: WebhookOutbox,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let templates = TemplateRegistry::load_dir(&config.templates_dir)
+            .map_err(|e| format!("Failed to load extraction templates: {}", e))?;
+
+        tracing::info!(prover = config.prover.as_str(), "setting up programs");
+        let prover = build_prover(config.prover);
+
+        let mut programs = HashMap::new();
+        for document_type in DocumentType::ALL {
+            let elf = program_elf(document_type);
+            let (pk, vk) = prover.setup(elf);
+            tracing::info!(
+                document_type = document_type.name(),
+                vkey = %vk.bytes32(),
+                "program set up"
+            );
+            programs.insert(document_type, Program { elf, pk, vk });
+        }
+
+        let rpc_url = config.chain.rpc_url.parse()?;
+        let chain_id = check_chain(&config, &ProviderBuilder::new().on_http(rpc_url)).await?;
+        let signer = load_signer(&config.signer, chain_id)?;
+        let signer_address = signer.address();
+        let provider = signer_provider(&config, signer)?;
+        let balance = provider
+            .get_balance(signer_address)
+            .await
+            .map_err(|e| rpc_error(&config, "Failed to get the signer balance", e))?;
+        tracing::info!(
+            signer = %signer_address,
+            balance_eth = %format_ether(balance),
+            "signer loaded"
+        );
+
+        let dir = &config.chain.deployments_dir;
+        let (contract_address, deployment_block) = match config.chain.contract_address {
+            Some(address) => (address, config.chain.deployment_block),
+            None => (
+                certificate_verifier_address(dir, chain_id)?,
+                match config.chain.deployment_block {
+                    Some(block) => Some(block),
+                    None => certificate_verifier_block(dir, chain_id)?,
+                },
+            ),
+        };
+        // Searching a local anvil node from its first block is cheap
+        let deployment_block = deployment_block.or((chain_id == ANVIL_CHAIN_ID).then_some(0));
+        if deployment_block.is_none() {
+            tracing::warn!(
+                "the block the certificate verifier was deployed in is unknown; set \
+                 chain.deployment_block or VERIFIER_BLOCK in the deployment record, as jobs for \
+                 documents already verified on-chain fail until then"
+            );
+        }
+        let verifier = CertificateVerifierInstance::new(contract_address, provider);
+        tracing::info!(contract = %verifier.address(), "certificate verifier");
+        check_program_keys(&config, &verifier, &programs).await?;
+
+        Ok(Self {
+            config: Arc::new(config),
+            jobs,
+            outbox,
+            prover: Arc::from(prover),
+            programs: Arc::new(programs),
+            templates: Arc::new(templates),
+            verifier: Arc::new(verifier),
+            chain_id,
+            deployment_block,
+        })
+    }
+
+    /// The program proving documents of `document_type`.
+    pub fn program(&self, document_type: DocumentType) -> &Program {
+        &self.programs[&document_type]
+    }
+}
+
+/// Build the prover backend. The network prover reads `NETWORK_PRIVATE_KEY`.
+fn build_prover(mode: ProverMode) -> Box<ProverBackend> {
+    match mode {
+        ProverMode::Network => Box::new(ProverClient::builder().network().build()),
+        ProverMode::Cpu => Box::new(ProverClient::builder().cpu().build()),
+        ProverMode::Mock => Box::new(ProverClient::builder().mock().build()),
+    }
+}
+
+/// Create a provider sending transactions with `signer`.
+fn signer_provider(
+    config: &Config,
+    signer: PrivateKeySigner,
+) -> Result<Arc<dyn Provider<Http<Client>>>, Box<dyn std::error::Error>> {
+    let wallet = EthereumWallet::from(signer);
+    Ok(Arc::new(
+        ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(wallet)
+            .on_http(config.chain.rpc_url.parse()?),
+    ))
+}
+
+/// Check that the RPC node is on the configured chain, and that the chain accepts the proofs of
+/// the prover backend. Mock proofs only pass the mock verifier, which is only deployed on a local
+/// anvil node. Returns the chain id.
+async fn check_chain(
+    config: &Config,
+    provider: &dyn Provider<Http<Client>>,
+) -> Result<u64, String> {
+    let chain_id = provider
+        .get_chain_id()
+        .await
+        .map_err(|e| rpc_error(config, "Failed to get the chain id", e))?;
+    if let Some(expected) = config.chain.chain_id {
+        if chain_id != expected {
+            return Err(format!(
+                "The RPC node is on chain {}, expected chain {}",
+                chain_id, expected
+            ));
+        }
+    }
+    if config.prover == ProverMode::Mock && chain_id != ANVIL_CHAIN_ID {
+        return Err(format!(
+            "Mock proofs can only be submitted to a local anvil node (chain {}), not chain {}",
+            ANVIL_CHAIN_ID, chain_id
+        ));
+    }
+    tracing::info!(rpc = %redact_url(&config.chain.rpc_url), chain_id, "connected to chain");
+    Ok(chain_id)
+}
+
+/// Check that the contract was deployed with the verification keys of the local programs, so
+/// that a rebuilt program is noticed before any proof is generated for it.
+async fn check_program_keys(
+    config: &Config,
+    verifier: &CertificateVerifier,
+    programs: &HashMap<DocumentType, Program>,
+) -> Result<(), String> {
+    for document_type in DocumentType::ALL {
+        let deployed = match document_type {
+            DocumentType::PropertyOwnership => verifier.propertyProgramVKey().call().await,
+            DocumentType::Gst => verifier.gstProgramVKey().call().await,
+        }
+        .map_err(|e| {
+            rpc_error(
+                config,
+                &format!(
+                    "Failed to read the {} program verification key of the contract",
+                    document_type.name()
+                ),
+                e,
+            )
+        })?
+        ._0
+        .to_string();
+        let local = programs[&document_type].vk.bytes32();
+        if !deployed.eq_ignore_ascii_case(&local) {
+            return Err(format!(
+                "The contract expects {} program verification key {}, but the local program has {}",
+                document_type.name(),
+                deployed,
+                local
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// An RPC failure, without the API key the RPC URL may hold.
+fn rpc_error(config: &Config, context: &str, e: impl std::fmt::Display) -> String {
+    redact_url_in(&format!("{}: {}", context, e), &config.chain.rpc_url)
+}
@@ -0,0 +1,423 @@
+//! SQLite job store
+//!
+//! Jobs are written through to an embedded SQLite database so that a proof paid for on the prover
+//! network survives a restart of the server. The document and the template of a job are kept
+//! until it finishes so that it can be resumed, and are deleted afterwards. Proofs are kept in
+//! their own table, so that polling a job does not load them. The webhook outbox keeps its
+//! deliveries in the same database.
+
+use std::sync::Mutex;
+
+use alloy::primitives::{Address, Bytes};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use sp1_sdk::SP1ProofWithPublicValues;
+use zkpdf_template_lib::{DocumentType, ExtractionTemplate};
+
+use crate::error::ErrorBody;
+use crate::jobs::{Job, JobInput, JobStage, ProofSystem};
+use crate::outbox::{Delivery, DeliveryState};
+
+/// Default path of the job database.
+pub const DEFAULT_JOBS_DB: &str = "jobs.db";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY,
+    document_type INTEGER NOT NULL,
+    stage TEXT NOT NULL,
+    proof_system TEXT NOT NULL DEFAULT 'groth16',
+    claimant TEXT NOT NULL,
+    property_id TEXT,
+    pdf_bytes BLOB,
+    template TEXT,
+    public_values BLOB,
+    transaction_hash TEXT,
+    block_number INTEGER,
+    gas_used INTEGER,
+    error TEXT,
+    webhook_delivered INTEGER NOT NULL DEFAULT 0,
+    webhook_error TEXT,
+    already_verified INTEGER NOT NULL DEFAULT 0,
+    gas_estimate INTEGER,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS job_proofs (
+    job_id TEXT PRIMARY KEY,
+    proof TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS webhooks (
+    id TEXT PRIMARY KEY,
+    job_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    body TEXT NOT NULL,
+    state TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    next_attempt_at INTEGER NOT NULL,
+    last_error TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS webhooks_due ON webhooks (state, next_attempt_at);
+CREATE INDEX IF NOT EXISTS webhooks_job ON webhooks (job_id);
+";
+
+const JOB_COLUMNS: &str = "id, document_type, stage, claimant, property_id, public_values, \
+    transaction_hash, error, webhook_delivered, created_at, updated_at, proof_system, \
+    block_number, gas_used, already_verified, gas_estimate, webhook_error";
+
+const DELIVERY_COLUMNS: &str =
+    "id, job_id, url, body, state, attempts, next_attempt_at, last_error, created_at, updated_at";
+
+/// Persistent storage of proving jobs.
+pub struct JobStore {
+    connection: Mutex<Connection>,
+}
+
+impl JobStore {
+    /// Open the database at `path`, creating it if needed.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let connection = Connection::open(path)
+            .map_err(|e| format!("Failed to open job database {}: {}", path, e))?;
+        connection
+            .execute_batch(SCHEMA)
+            .map_err(|e| format!("Failed to create job database schema: {}", e))?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Store a new job with the document to prove.
+    pub fn insert(&self, job: &Job, input: &JobInput) -> Result<(), String> {
+        let template = serde_json::to_string(&input.template).map_err(|e| e.to_string())?;
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO jobs (id, document_type, stage, proof_system, claimant, property_id, \
+                 pdf_bytes, template, webhook_delivered, created_at, updated_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    job.id,
+                    job.document_type.id(),
+                    job.stage.as_str(),
+                    job.proof_system.as_str(),
+                    job.claimant.to_string(),
+                    job.property_id,
+                    input.pdf_bytes,
+                    template,
+                    job.webhook_delivered,
+                    job.created_at,
+                    job.updated_at,
+                ],
+            )
+            .map_err(|e| format!("Failed to store job: {}", e))?;
+        Ok(())
+    }
+
+    /// Save the progress of a job.
+    pub fn save(&self, job: &Job) -> Result<(), String> {
+        let error = job
+            .error
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let webhook_error = job
+            .webhook_error
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE jobs SET stage = ?2, public_values = ?3, transaction_hash = ?4, error = ?5, \
+                 webhook_delivered = ?6, updated_at = ?7, block_number = ?8, gas_used = ?9, \
+                 already_verified = ?10, gas_estimate = ?11, webhook_error = ?12 WHERE id = ?1",
+                params![
+                    job.id,
+                    job.stage.as_str(),
+                    job.public_values.as_ref().map(|bytes| bytes.to_vec()),
+                    job.transaction_hash,
+                    error,
+                    job.webhook_delivered,
+                    job.updated_at,
+                    job.block_number,
+                    job.gas_used,
+                    job.already_verified,
+                    job.gas_estimate,
+                    webhook_error,
+                ],
+            )
+            .map_err(|e| format!("Failed to save job {}: {}", job.id, e))?;
+        Ok(())
+    }
+
+    /// Look up a job by id.
+    pub fn get(&self, id: &str) -> Result<Option<Job>, String> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+                params![id],
+                job_from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load job {}: {}", id, e))
+    }
+
+    /// Store the proof generated by a job.
+    pub fn save_proof(&self, id: &str, proof: &SP1ProofWithPublicValues) -> Result<(), String> {
+        let proof = serde_json::to_string(proof).map_err(|e| e.to_string())?;
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT OR REPLACE INTO job_proofs (job_id, proof) VALUES (?1, ?2)",
+                params![id, proof],
+            )
+            .map_err(|e| format!("Failed to save the proof of job {}: {}", id, e))?;
+        Ok(())
+    }
+
+    /// The proof generated by a job, once proving has finished.
+    pub fn proof(&self, id: &str) -> Result<Option<SP1ProofWithPublicValues>, String> {
+        let proof = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT proof FROM job_proofs WHERE job_id = ?1",
+                params![id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load the proof of job {}: {}", id, e))?;
+        proof
+            .map(|proof| serde_json::from_str(&proof))
+            .transpose()
+            .map_err(|e| format!("Invalid proof stored for job {}: {}", id, e))
+    }
+
+    /// The document and template of a job, until the job finishes.
+    pub fn input(&self, id: &str) -> Result<Option<JobInput>, String> {
+        let row = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT pdf_bytes, template FROM jobs \
+                 WHERE id = ?1 AND pdf_bytes IS NOT NULL AND template IS NOT NULL",
+                params![id],
+                |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load the document of job {}: {}", id, e))?;
+
+        row.map(|(pdf_bytes, template)| {
+            let template: ExtractionTemplate =
+                serde_json::from_str(&template).map_err(|e| e.to_string())?;
+            Ok(JobInput {
+                pdf_bytes,
+                template,
+            })
+        })
+        .transpose()
+    }
+
+    /// Delete the document and template of a finished job.
+    pub fn clear_input(&self, id: &str) -> Result<(), String> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE jobs SET pdf_bytes = NULL, template = NULL WHERE id = ?1",
+                params![id],
+            )
+            .map_err(|e| format!("Failed to delete the document of job {}: {}", id, e))?;
+        Ok(())
+    }
+
+    /// Ids of the jobs to resume at startup: jobs that have not finished and, when a status
+    /// webhook is configured (`notify`), property jobs proven for on-chain verification whose
+    /// outcome has not been queued for it.
+    pub fn resumable(&self, notify: bool) -> Result<Vec<String>, String> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(
+                "SELECT id FROM jobs \
+                 WHERE stage IN ('queued', 'executing', 'proving', 'submitting') \
+                 OR (?2 AND document_type = ?1 AND proof_system IN ('groth16', 'plonk') \
+                     AND webhook_delivered = 0 \
+                     AND NOT EXISTS (SELECT 1 FROM webhooks WHERE webhooks.job_id = jobs.id)) \
+                 ORDER BY created_at",
+            )
+            .map_err(|e| e.to_string())?;
+        let ids = statement
+            .query_map(
+                params![DocumentType::PropertyOwnership.id(), notify],
+                |row| row.get(0),
+            )
+            .and_then(|rows| rows.collect())
+            .map_err(|e| format!("Failed to load unfinished jobs: {}", e))?;
+        Ok(ids)
+    }
+
+    /// Store a new webhook delivery. Returns `false` if a delivery with the same id exists.
+    pub fn insert_delivery(&self, delivery: &Delivery) -> Result<bool, String> {
+        let inserted = self
+            .connection
+            .lock()
+            .unwrap()
+            .execute(
+                &format!(
+                    "INSERT OR IGNORE INTO webhooks ({}) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    DELIVERY_COLUMNS
+                ),
+                params![
+                    delivery.id,
+                    delivery.job_id,
+                    delivery.url,
+                    delivery.body,
+                    delivery.state.as_str(),
+                    delivery.attempts,
+                    delivery.next_attempt_at,
+                    delivery.last_error,
+                    delivery.created_at,
+                    delivery.updated_at,
+                ],
+            )
+            .map_err(|e| format!("Failed to queue webhook delivery: {}", e))?;
+        Ok(inserted > 0)
+    }
+
+    /// Save the attempts of a webhook delivery.
+    pub fn save_delivery(&self, delivery: &Delivery) -> Result<(), String> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE webhooks SET state = ?2, attempts = ?3, next_attempt_at = ?4, \
+                 last_error = ?5, updated_at = ?6 WHERE id = ?1",
+                params![
+                    delivery.id,
+                    delivery.state.as_str(),
+                    delivery.attempts,
+                    delivery.next_attempt_at,
+                    delivery.last_error,
+                    delivery.updated_at,
+                ],
+            )
+            .map_err(|e| format!("Failed to save webhook delivery {}: {}", delivery.id, e))?;
+        Ok(())
+    }
+
+    /// Look up a webhook delivery by id.
+    pub fn delivery(&self, id: &str) -> Result<Option<Delivery>, String> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {} FROM webhooks WHERE id = ?1", DELIVERY_COLUMNS),
+                params![id],
+                delivery_from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load webhook delivery {}: {}", id, e))
+    }
+
+    /// Pending webhook deliveries whose next attempt is due at `now`, oldest first.
+    pub fn due_deliveries(&self, now: u64) -> Result<Vec<Delivery>, String> {
+        self.query_deliveries("state = 'pending' AND next_attempt_at <= ?1", params![now])
+    }
+
+    /// Webhook deliveries in `state`, oldest first.
+    pub fn deliveries(&self, state: DeliveryState) -> Result<Vec<Delivery>, String> {
+        self.query_deliveries("state = ?1", params![state.as_str()])
+    }
+
+    fn query_deliveries(
+        &self,
+        condition: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<Delivery>, String> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {} FROM webhooks WHERE {} ORDER BY created_at",
+                DELIVERY_COLUMNS, condition
+            ))
+            .map_err(|e| e.to_string())?;
+        let deliveries = statement
+            .query_map(params, delivery_from_row)
+            .and_then(|rows| rows.collect())
+            .map_err(|e| format!("Failed to load webhook deliveries: {}", e))?;
+        Ok(deliveries)
+    }
+}
+
+fn job_from_row(row: &Row) -> rusqlite::Result<Job> {
+    let document_type: u8 = row.get(1)?;
+    let stage: String = row.get(2)?;
+    let claimant: String = row.get(3)?;
+    let public_values: Option<Vec<u8>> = row.get(5)?;
+    let error: Option<String> = row.get(7)?;
+    let webhook_error: Option<String> = row.get(16)?;
+    let proof_system: String = row.get(11)?;
+
+    Ok(Job {
+        id: row.get(0)?,
+        document_type: DocumentType::from_id(document_type)
+            .ok_or_else(|| invalid_column(1, format!("unknown document type {}", document_type)))?,
+        stage: JobStage::from_name(&stage)
+            .ok_or_else(|| invalid_column(2, format!("unknown stage {}", stage)))?,
+        proof_system: ProofSystem::from_name(&proof_system)
+            .ok_or_else(|| invalid_column(11, format!("unknown proof system {}", proof_system)))?,
+        claimant: claimant
+            .parse::<Address>()
+            .map_err(|e| invalid_column(3, e.to_string()))?,
+        property_id: row.get(4)?,
+        public_values: public_values.map(Bytes::from),
+        transaction_hash: row.get(6)?,
+        block_number: row.get(12)?,
+        gas_used: row.get(13)?,
+        error: error
+            .map(|error| serde_json::from_str::<ErrorBody>(&error))
+            .transpose()
+            .map_err(|e| invalid_column(7, e.to_string()))?,
+        already_verified: row.get(14)?,
+        gas_estimate: row.get(15)?,
+        webhook_delivered: row.get(8)?,
+        webhook_error: webhook_error
+            .map(|error| serde_json::from_str::<ErrorBody>(&error))
+            .transpose()
+            .map_err(|e| invalid_column(16, e.to_string()))?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn delivery_from_row(row: &Row) -> rusqlite::Result<Delivery> {
+    let state: String = row.get(4)?;
+    Ok(Delivery {
+        id: row.get(0)?,
+        job_id: row.get(1)?,
+        url: row.get(2)?,
+        body: row.get(3)?,
+        state: DeliveryState::from_name(&state)
+            .ok_or_else(|| invalid_column(4, format!("unknown delivery state {}", state)))?,
+        attempts: row.get(5)?,
+        next_attempt_at: row.get(6)?,
+        last_error: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+fn invalid_column(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
@@ -0,0 +1,484 @@
+use std::sync::Arc;
+
+use alloy::primitives::{address, Address, Bytes, B256};
+use alloy::signers::{local::PrivateKeySigner, SignerSync};
+use alloy_sol_types::SolType;
+use axum::http;
+use reqwest::StatusCode;
+use zkpdf_template_lib::{DocumentType, ExtractionTemplate, PublicValuesStruct};
+
+use crate::admin::constant_time_eq;
+use crate::config::{Config, SignerConfig};
+use crate::error::{ApiError, ErrorBody};
+use crate::jobs::{Job, JobInput, JobQueue, JobStage, ProofSystem};
+use crate::outbox::{is_permanent_failure, retry_delay, signature};
+use crate::ownership::{check_wallet_signature, claim_message};
+use crate::preflight::gas_limit;
+use crate::signer::{load_signer, SignerSource};
+use crate::state::ANVIL_CHAIN_ID;
+use crate::store::JobStore;
+use crate::upload::check_pdf;
+use crate::webhook::{status_update, Verifier};
+
+const ANVIL_DEV_ADDRESS: Address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+
+#[test]
+fn test_check_wallet_signature() {
+    let wallet: PrivateKeySigner =
+        "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d"
+            .parse()
+            .unwrap();
+    let message = claim_message(B256::repeat_byte(0x11), Some("42"));
+    let signed = wallet.sign_message_sync(message.as_bytes()).unwrap();
+    let signed = format!("0x{}", hex::encode(signed.as_bytes()));
+
+    assert!(check_wallet_signature(&message, Some(&signed), wallet.address()).is_ok());
+
+    let other_message = claim_message(B256::repeat_byte(0x11), Some("43"));
+    assert!(check_wallet_signature(&other_message, Some(&signed), wallet.address()).is_err());
+    assert!(check_wallet_signature(&message, Some(&signed), ANVIL_DEV_ADDRESS).is_err());
+
+    for signature in [None, Some("0x1234"), Some("not a signature")] {
+        assert_eq!(
+            check_wallet_signature(&message, signature, wallet.address())
+                .unwrap_err()
+                .code(),
+            "invalid_wallet_signature"
+        );
+    }
+}
+
+#[test]
+fn test_check_pdf() {
+    assert!(check_pdf(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3").is_ok());
+
+    let mut junk_first = vec![b' '; 1000];
+    junk_first.extend_from_slice(b"%PDF-1.4");
+    assert!(check_pdf(&junk_first).is_ok());
+
+    let mut header_too_late = vec![b' '; 1024];
+    header_too_late.extend_from_slice(b"%PDF-1.4");
+    assert_eq!(check_pdf(&header_too_late).unwrap_err().code(), "not_a_pdf");
+
+    assert_eq!(check_pdf(b"").unwrap_err().code(), "not_a_pdf");
+    assert_eq!(check_pdf(b"PK\x03\x04").unwrap_err().code(), "not_a_pdf");
+}
+
+#[test]
+fn test_error_bodies() {
+    let error = ApiError::DocumentRejected { error_code: 3 };
+    assert_eq!(error.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    let body = error.body();
+    assert_eq!(body.code, "document_rejected");
+    assert_eq!(body.details, Some(serde_json::json!({ "error_code": 3 })));
+
+    let error = ApiError::JobNotFound("job-1".to_string());
+    assert_eq!(error.status(), http::StatusCode::NOT_FOUND);
+    assert_eq!(error.body().message, "Unknown job: job-1");
+
+    let rejection =
+        ApiError::from_rejection(http::StatusCode::PAYLOAD_TOO_LARGE, "too big".to_string());
+    assert_eq!(rejection.code(), "payload_too_large");
+    let rejection = ApiError::from_rejection(http::StatusCode::BAD_REQUEST, "bad".to_string());
+    assert_eq!(rejection.code(), "invalid_request");
+}
+
+#[test]
+fn test_listing_already_backed_error() {
+    let error = ApiError::ListingAlreadyBacked {
+        property_id: "42".to_string(),
+        document_commitment: format!("{}", B256::repeat_byte(0x33)),
+    };
+    assert_eq!(error.status(), http::StatusCode::CONFLICT);
+    let body = error.body();
+    assert_eq!(body.code, "listing_already_backed");
+    assert_eq!(body.details.unwrap()["property_id"], "42");
+}
+
+#[test]
+fn test_template_not_approved_error() {
+    let error = ApiError::TemplateNotApproved {
+        template_hash: format!("{}", B256::repeat_byte(0x22)),
+    };
+    assert_eq!(error.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    let body = error.body();
+    assert_eq!(body.code, "template_not_approved");
+    assert_eq!(
+        body.details,
+        Some(serde_json::json!({ "template_hash": format!("{}", B256::repeat_byte(0x22)) }))
+    );
+}
+
+/// A config that validates, with settings read from variables no other test sets.
+fn valid_config() -> Config {
+    let mut config = Config::default();
+    config.signer.private_key_env = "TEST_CONFIG_UNSET_KEY".to_string();
+    config.webhooks.secret_env = "TEST_CONFIG_UNSET_SECRET".to_string();
+    config
+}
+
+#[test]
+fn test_config_validate() {
+    assert_eq!(valid_config().validate(), Ok(()));
+
+    let mut config = valid_config();
+    config.chain.rpc_url = "not a url".to_string();
+    assert!(config.validate().unwrap_err().contains("Invalid RPC URL"));
+
+    let mut config = valid_config();
+    config.cors_origins = vec!["bad\norigin".to_string()];
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .contains("Invalid CORS origin"));
+
+    let mut config = valid_config();
+    config.webhooks.max_attempts = 0;
+    assert!(config.validate().is_err());
+
+    let mut config = valid_config();
+    config.webhooks.payload_version = 3;
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .contains("Unsupported webhooks.payload_version 3"));
+
+    let mut config = valid_config();
+    config.limits.max_concurrent_jobs = 0;
+    assert!(config.validate().is_err());
+
+    let mut config = valid_config();
+    config.signer.password_file = Some("password.txt".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_config_validate_requires_webhook_secret() {
+    let mut config = valid_config();
+    config.webhooks.status_update_url = Some("https://example.com/webhook".to_string());
+    assert!(config
+        .validate()
+        .unwrap_err()
+        .contains("TEST_CONFIG_UNSET_SECRET is missing"));
+
+    std::env::set_var("TEST_CONFIG_SECRET", "secret");
+    config.webhooks.secret_env = "TEST_CONFIG_SECRET".to_string();
+    assert_eq!(config.validate(), Ok(()));
+}
+
+const SEPOLIA_CHAIN_ID: u64 = 11155111;
+
+/// Mnemonic of the anvil development accounts; its first key is anvil's first development key.
+const ANVIL_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+/// A signer config reading the private key from `private_key_env`. Every test uses its own
+/// variables, as tests run in parallel in the same process.
+fn signer_config(private_key_env: &str) -> SignerConfig {
+    SignerConfig {
+        private_key_env: private_key_env.to_string(),
+        ..SignerConfig::default()
+    }
+}
+
+#[test]
+fn test_load_signer_uses_anvil_key_only_on_anvil() {
+    let config = signer_config("TEST_SIGNER_UNSET_KEY");
+
+    let signer = load_signer(&config, ANVIL_CHAIN_ID).unwrap();
+    assert_eq!(signer.address(), ANVIL_DEV_ADDRESS);
+
+    let error = load_signer(&config, SEPOLIA_CHAIN_ID).unwrap_err();
+    assert!(error.contains("No signer configured for chain 11155111"));
+}
+
+#[test]
+fn test_load_signer_refuses_anvil_key_on_other_chains() {
+    std::env::set_var(
+        "TEST_SIGNER_ANVIL_KEY",
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    );
+    let config = signer_config("TEST_SIGNER_ANVIL_KEY");
+
+    assert!(load_signer(&config, ANVIL_CHAIN_ID).is_ok());
+    let error = load_signer(&config, SEPOLIA_CHAIN_ID).unwrap_err();
+    assert!(error.contains("Refusing to sign with anvil's public development key"));
+}
+
+#[test]
+fn test_load_signer_reads_private_key() {
+    let key = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+    std::env::set_var("TEST_SIGNER_PRIVATE_KEY", format!("{}\n", key));
+    let config = signer_config("TEST_SIGNER_PRIVATE_KEY");
+
+    let signer = load_signer(&config, SEPOLIA_CHAIN_ID).unwrap();
+    assert_eq!(
+        signer.address(),
+        key.parse::<PrivateKeySigner>().unwrap().address()
+    );
+}
+
+#[test]
+fn test_signer_source_selection() {
+    let keystore = SignerConfig {
+        keystore: Some("keystore.json".to_string()),
+        password_file: Some("password.txt".to_string()),
+        ..signer_config("TEST_SIGNER_SELECTION_KEY")
+    };
+    assert_eq!(
+        SignerSource::from_config(&keystore).unwrap(),
+        Some(SignerSource::Keystore {
+            path: "keystore.json".to_string(),
+            password_file: "password.txt".to_string(),
+        })
+    );
+
+    let no_password = SignerConfig {
+        password_file: None,
+        ..keystore.clone()
+    };
+    assert!(SignerSource::from_config(&no_password).is_err());
+
+    let no_keystore = SignerConfig {
+        keystore: None,
+        ..keystore.clone()
+    };
+    assert!(SignerSource::from_config(&no_keystore).is_err());
+
+    let both = SignerConfig {
+        mnemonic_env: Some("TEST_SIGNER_SELECTION_MNEMONIC".to_string()),
+        ..keystore
+    };
+    assert!(SignerSource::from_config(&both).is_err());
+
+    let unset_mnemonic = SignerConfig {
+        mnemonic_env: Some("TEST_SIGNER_UNSET_MNEMONIC".to_string()),
+        ..signer_config("TEST_SIGNER_SELECTION_KEY")
+    };
+    assert!(SignerSource::from_config(&unset_mnemonic).is_err());
+
+    let none = signer_config("TEST_SIGNER_SELECTION_KEY");
+    assert_eq!(SignerSource::from_config(&none).unwrap(), None);
+}
+
+#[test]
+fn test_load_signer_derives_mnemonic_key() {
+    std::env::set_var("TEST_SIGNER_MNEMONIC", ANVIL_MNEMONIC);
+    let config = SignerConfig {
+        mnemonic_env: Some("TEST_SIGNER_MNEMONIC".to_string()),
+        ..signer_config("TEST_SIGNER_MNEMONIC_KEY")
+    };
+
+    let signer = load_signer(&config, ANVIL_CHAIN_ID).unwrap();
+    assert_eq!(signer.address(), ANVIL_DEV_ADDRESS);
+    // The first key of the anvil mnemonic is the public development key
+    assert!(load_signer(&config, SEPOLIA_CHAIN_ID).is_err());
+
+    let second_account = SignerConfig {
+        derivation_path: "m/44'/60'/0'/0/1".to_string(),
+        ..config
+    };
+    let signer = load_signer(&second_account, SEPOLIA_CHAIN_ID).unwrap();
+    assert_eq!(
+        signer.address(),
+        address!("70997970C51812dc3A010C7d01b50e0d17dc79C8")
+    );
+}
+
+#[test]
+fn test_webhook_signature() {
+    assert_eq!(
+        signature(b"secret", 1700000000, r#"{"status":true}"#),
+        "sha256=ddbe57ecfa840727ed096c1ef57ee3a936d0bd810abda302a942f87f624521d3"
+    );
+    assert_ne!(
+        signature(b"secret", 1700000001, r#"{"status":true}"#),
+        signature(b"secret", 1700000000, r#"{"status":true}"#)
+    );
+}
+
+#[test]
+fn test_retry_delay_backs_off_exponentially() {
+    assert_eq!(retry_delay(1), 10);
+    assert_eq!(retry_delay(2), 20);
+    assert_eq!(retry_delay(5), 160);
+    assert_eq!(retry_delay(10), 60 * 60);
+    assert_eq!(retry_delay(u32::MAX), 60 * 60);
+}
+
+#[test]
+fn test_permanent_webhook_failures() {
+    assert!(is_permanent_failure(StatusCode::BAD_REQUEST));
+    assert!(is_permanent_failure(StatusCode::NOT_FOUND));
+    assert!(!is_permanent_failure(StatusCode::REQUEST_TIMEOUT));
+    assert!(!is_permanent_failure(StatusCode::TOO_MANY_REQUESTS));
+    assert!(!is_permanent_failure(StatusCode::INTERNAL_SERVER_ERROR));
+}
+
+#[test]
+fn test_constant_time_eq() {
+    assert!(constant_time_eq(b"token", b"token"));
+    assert!(!constant_time_eq(b"token", b"tokem"));
+    assert!(!constant_time_eq(b"token", b"token2"));
+}
+
+const VKEY: &str = "0x00a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f";
+const CONTRACT: Address = address!("5FbDB2315678afecb367f032d93F642f64180aa3");
+
+/// A finished property job committing `public_values`.
+fn property_job(stage: JobStage, public_values: Option<PublicValuesStruct>) -> Job {
+    Job {
+        id: "job-1".to_string(),
+        document_type: DocumentType::PropertyOwnership,
+        stage,
+        proof_system: ProofSystem::Groth16,
+        claimant: ANVIL_DEV_ADDRESS,
+        property_id: Some("requested".to_string()),
+        gas_estimate: Some(250_000),
+        transaction_hash: Some(format!("{}", B256::repeat_byte(0xab))),
+        block_number: Some(7),
+        gas_used: Some(240_000),
+        error: None,
+        already_verified: false,
+        webhook_delivered: false,
+        webhook_error: None,
+        public_values: public_values
+            .map(|public_values| Bytes::from(PublicValuesStruct::abi_encode(&public_values))),
+        created_at: 1700000000,
+        updated_at: 1700000000,
+    }
+}
+
+fn committed_values() -> PublicValuesStruct {
+    PublicValuesStruct {
+        property_number: "1".to_string(),
+        owner_name: "Mr. Ajay".to_string(),
+        signature_valid: true,
+        property_id: "committed".to_string(),
+        claimant: ANVIL_DEV_ADDRESS.into_array().into(),
+        ..Default::default()
+    }
+}
+
+fn verifier() -> Verifier<'static> {
+    Verifier {
+        vkey: VKEY,
+        chain_id: ANVIL_CHAIN_ID,
+        contract: CONTRACT,
+    }
+}
+
+#[test]
+fn test_status_update_v1() {
+    let job = property_job(JobStage::Completed, Some(committed_values()));
+    let update = status_update(&job, 1, &verifier()).unwrap();
+
+    assert_eq!(
+        update,
+        serde_json::json!({
+            "status": true,
+            "transaction_hash": job.transaction_hash,
+            "property_id": "committed",
+            "property_number": "1",
+            "owner_name": "Mr. Ajay",
+            "signature_valid": true,
+        })
+    );
+}
+
+#[test]
+fn test_status_update_v1_of_failed_job() {
+    let mut job = property_job(JobStage::Failed, None);
+    job.transaction_hash = None;
+    let update = status_update(&job, 1, &verifier()).unwrap();
+
+    assert_eq!(update["status"], false);
+    assert_eq!(update["property_id"], "requested");
+    assert!(update["owner_name"].is_null());
+}
+
+#[test]
+fn test_status_update_v2() {
+    let job = property_job(JobStage::Completed, Some(committed_values()));
+    let update = status_update(&job, 2, &verifier()).unwrap();
+
+    assert_eq!(update["version"], 2);
+    assert_eq!(update["event"], "property.status");
+    assert_eq!(update["job_id"], "job-1");
+    assert_eq!(update["status"], true);
+    assert_eq!(update["property_id"], "committed");
+    assert_eq!(update["vkey"], VKEY);
+    assert_eq!(update["public_values"]["owner_name"], "Mr. Ajay");
+    assert_eq!(
+        update["receipt"],
+        serde_json::json!({
+            "chain_id": ANVIL_CHAIN_ID,
+            "contract": CONTRACT,
+            "transaction_hash": job.transaction_hash,
+            "block_number": 7,
+            "gas_used": 240_000,
+        })
+    );
+    assert_eq!(update["already_verified"], false);
+    assert!(update["error"].is_null());
+}
+
+#[test]
+fn test_status_update_v2_of_failed_job() {
+    let mut job = property_job(JobStage::Failed, None);
+    job.transaction_hash = None;
+    job.error = Some(ErrorBody {
+        code: "transaction_send_failed".to_string(),
+        message: "Failed to send the transaction".to_string(),
+        details: None,
+    });
+    let update = status_update(&job, 2, &verifier()).unwrap();
+
+    assert_eq!(update["status"], false);
+    assert!(update["receipt"].is_null());
+    assert!(update["public_values"].is_null());
+    assert_eq!(update["error"]["code"], "transaction_send_failed");
+}
+
+#[test]
+fn test_status_update_rejects_unknown_version() {
+    let job = property_job(JobStage::Completed, None);
+    assert!(status_update(&job, 3, &verifier()).is_err());
+}
+
+#[test]
+fn test_gas_limit_adds_margin() {
+    assert_eq!(gas_limit(100_000), 120_000);
+    assert_eq!(gas_limit(u64::MAX), u64::MAX);
+}
+
+/// Queue a Groth16 property job.
+fn queue_property_job(jobs: &JobQueue) -> String {
+    let input = JobInput {
+        pdf_bytes: b"%PDF-1.7".to_vec(),
+        template: ExtractionTemplate::default_for(DocumentType::PropertyOwnership),
+    };
+    jobs.create(
+        DocumentType::PropertyOwnership,
+        ProofSystem::Groth16,
+        ANVIL_DEV_ADDRESS,
+        Some("42".to_string()),
+        input,
+    )
+    .unwrap()
+    .id
+}
+
+#[test]
+fn test_completed_jobs_are_only_resumed_to_notify() {
+    let jobs = JobQueue::new(Arc::new(JobStore::open(":memory:").unwrap()), 1);
+    let queued = queue_property_job(&jobs);
+    let completed = queue_property_job(&jobs);
+    jobs.set_stage(&completed, JobStage::Completed);
+
+    assert_eq!(jobs.resumable(false).unwrap(), vec![queued.clone()]);
+    let mut resumable = jobs.resumable(true).unwrap();
+    resumable.sort();
+    let mut expected = vec![queued, completed];
+    expected.sort();
+    assert_eq!(resumable, expected);
+}
@@ -0,0 +1,133 @@
+//! PDF uploads
+//!
+//! Documents are uploaded either as `multipart/form-data`, with the PDF in a `pdf` file field and
+//! the other request fields as text fields, or as JSON with the PDF base64-encoded in
+//! `pdf_base64`. The JSON integer array `pdf_bytes` of the first API version is still accepted.
+//! Request bodies are limited to `MAX_UPLOAD_BYTES`.
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Multipart, Request},
+    http::header,
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::error::ApiError;
+
+/// Default limit of request bodies, in bytes.
+pub const DEFAULT_MAX_UPLOAD_BYTES: usize = 10 * 1024 * 1024;
+
+/// Name of the multipart field holding the PDF.
+const PDF_FIELD: &str = "pdf";
+
+/// A PDF upload with the request fields `T`.
+pub struct Upload<T> {
+    pub pdf_bytes: Vec<u8>,
+    pub fields: T,
+}
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Upload<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_multipart = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("multipart/form-data"));
+
+        let (pdf_bytes, fields) = if is_multipart {
+            let multipart = Multipart::from_request(req, state)
+                .await
+                .map_err(|e| ApiError::from_rejection(e.status(), e.body_text()))?;
+            read_multipart(multipart).await?
+        } else {
+            let Json(fields) = Json::<Map<String, Value>>::from_request(req, state)
+                .await
+                .map_err(|e| ApiError::from_rejection(e.status(), e.body_text()))?;
+            read_json(fields)?
+        };
+
+        check_pdf(&pdf_bytes)?;
+        let fields = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| ApiError::InvalidRequest(format!("Invalid request: {}", e)))?;
+        Ok(Upload { pdf_bytes, fields })
+    }
+}
+
+async fn read_multipart(
+    mut multipart: Multipart,
+) -> Result<(Vec<u8>, Map<String, Value>), ApiError> {
+    let mut pdf_bytes = None;
+    let mut fields = Map::new();
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::from_rejection(e.status(), e.body_text()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        if name == PDF_FIELD {
+            let bytes = field
+                .bytes()
+                .await
+                .map_err(|e| ApiError::from_rejection(e.status(), e.body_text()))?;
+            pdf_bytes = Some(bytes.to_vec());
+        } else {
+            let text = field
+                .text()
+                .await
+                .map_err(|e| ApiError::from_rejection(e.status(), e.body_text()))?;
+            fields.insert(name, Value::String(text));
+        }
+    }
+
+    let pdf_bytes = pdf_bytes
+        .ok_or_else(|| ApiError::InvalidRequest(format!("Missing `{}` file field", PDF_FIELD)))?;
+    Ok((pdf_bytes, fields))
+}
+
+fn read_json(mut fields: Map<String, Value>) -> Result<(Vec<u8>, Map<String, Value>), ApiError> {
+    let pdf_bytes = match (fields.remove("pdf_base64"), fields.remove("pdf_bytes")) {
+        (Some(Value::String(encoded)), None) => STANDARD.decode(encoded).map_err(|e| {
+            ApiError::InvalidRequest(format!("`pdf_base64` is not valid base64: {}", e))
+        })?,
+        (Some(_), None) => {
+            return Err(ApiError::InvalidRequest(
+                "`pdf_base64` must be a base64 string".to_string(),
+            ))
+        }
+        (None, Some(bytes)) => serde_json::from_value(bytes).map_err(|e| {
+            ApiError::InvalidRequest(format!("`pdf_bytes` is not a byte array: {}", e))
+        })?,
+        (None, None) => {
+            return Err(ApiError::InvalidRequest(
+                "Missing `pdf_base64` field".to_string(),
+            ))
+        }
+        (Some(_), Some(_)) => {
+            return Err(ApiError::InvalidRequest(
+                "Send the PDF in either `pdf_base64` or `pdf_bytes`, not both".to_string(),
+            ))
+        }
+    };
+    Ok((pdf_bytes, fields))
+}
+
+/// Check that the upload is a PDF. The header may follow up to 1024 bytes of junk, as readers
+/// accept.
+pub fn check_pdf(pdf_bytes: &[u8]) -> Result<(), ApiError> {
+    let head = &pdf_bytes[..pdf_bytes.len().min(1024)];
+    if !head.windows(5).any(|window| window == b"%PDF-") {
+        return Err(ApiError::NotAPdf);
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+//! Documents already verified on-chain
+//!
+//! Proving a document takes minutes, but its commitment only depends on the signed fields and the
+//! signature, so it can be computed natively in milliseconds. Before proving a property document,
+//! the server verifies it outside the zkVM and asks the certificate verifier contract whether the
+//! commitment is already verified. If it is, for the job's wallet and listing, the job reuses the
+//! existing record and the transaction that created it instead of proving the document again.
+//!
+//! The contract links a certificate to a single wallet and listing, and a listing to a single
+//! certificate, so a document verified for another wallet or listing, or for a listing backed by
+//! another document, is rejected without proving: its proof would be rejected on-chain.
+
+use alloy::primitives::{keccak256, Address, B256};
+use alloy::providers::Provider;
+use zkpdf_template_lib::{
+    utils::generate_property_commitment, DocumentVerifier, PropertyOwnershipCertificate,
+    PropertyOwnershipVerifier,
+};
+use zkpdf_template_script::logging::pii;
+
+use crate::error::ApiError;
+use crate::jobs::{Job, JobInput};
+use crate::state::AppState;
+
+/// The on-chain record of a property document.
+pub struct VerifiedProperty {
+    pub document_commitment: B256,
+    /// The transaction that verified the document.
+    pub transaction_hash: String,
+    pub block_number: Option<u64>,
+    pub gas_used: Option<u64>,
+}
+
+/// Look up the on-chain record of the document of a property job. Returns `None` when the
+/// document is not verified yet or cannot be checked natively, so that it is proven as usual. Fails
+/// with `listing_already_backed` when the listing is backed by another document, and with
+/// `record_lookup_failed` when the document is verified but its transaction cannot be found.
+pub async fn find_verified_property(
+    state: &AppState,
+    job: &Job,
+    input: &JobInput,
+) -> Result<Option<VerifiedProperty>, ApiError> {
+    // Parsing the PDF and checking its signature blocks
+    let pdf_bytes = input.pdf_bytes.clone();
+    let template = input.template.clone();
+    let certificate = tokio::task::spawn_blocking(move || {
+        PropertyOwnershipVerifier::verify(pdf_bytes, &template)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("Verification task failed: {}", e)))?;
+    let certificate = match certificate {
+        Ok(certificate) => certificate,
+        // The program reports why the document is rejected
+        Err(_) => return Ok(None),
+    };
+    let document_commitment = B256::from(generate_property_commitment(&certificate));
+    let property_id = job.property_id.as_deref().unwrap_or_default();
+
+    // The checks only save proving time, so a failing node does not fail the job
+    match listing_certificate(state, property_id).await {
+        Ok(Some(backing)) if backing != document_commitment => {
+            return Err(ApiError::ListingAlreadyBacked {
+                property_id: property_id.to_string(),
+                document_commitment: backing.to_string(),
+            });
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "failed to check the document backing the listing"),
+    }
+    let record = match property_record(state, document_commitment).await {
+        Ok(record) => record,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to check whether the document is verified");
+            return Ok(None);
+        }
+    };
+    let Some((claimant, listing)) = record else {
+        return Ok(None);
+    };
+
+    if claimant != job.claimant {
+        return Err(ApiError::AlreadyVerified {
+            document_commitment: document_commitment.to_string(),
+            reason: "for another wallet".to_string(),
+        });
+    }
+    if listing != property_id {
+        return Err(ApiError::AlreadyVerified {
+            document_commitment: document_commitment.to_string(),
+            reason: format!("for listing {}", listing),
+        });
+    }
+
+    let verified = find_transaction(state, job, &certificate, document_commitment)
+        .await
+        .map_err(|cause| ApiError::RecordLookupFailed {
+            document_commitment: document_commitment.to_string(),
+            cause,
+        })?;
+    tracing::info!(
+        %document_commitment,
+        property_number = %pii(&certificate.property_number),
+        transaction_hash = %verified.transaction_hash,
+        "document already verified on-chain, skipping proving"
+    );
+    Ok(Some(verified))
+}
+
+/// The commitment of the document a listing is backed by, if any.
+async fn listing_certificate(state: &AppState, property_id: &str) -> Result<Option<B256>, String> {
+    let document_commitment = state
+        .verifier
+        .listingCertificates(property_id.to_string())
+        .call()
+        .await
+        .map_err(|e| e.to_string())?
+        ._0;
+    Ok((!document_commitment.is_zero()).then_some(document_commitment))
+}
+
+/// The claimant and listing a document commitment is verified for, if it is verified.
+async fn property_record(
+    state: &AppState,
+    document_commitment: B256,
+) -> Result<Option<(Address, String)>, String> {
+    let contract = &state.verifier;
+    let verified = contract
+        .isPropertyVerified(document_commitment)
+        .call()
+        .await
+        .map_err(|e| e.to_string())?
+        ._0;
+    if !verified {
+        return Ok(None);
+    }
+    let claimant = contract
+        .propertyClaimants(document_commitment)
+        .call()
+        .await
+        .map_err(|e| e.to_string())?
+        ._0;
+    let listing = contract
+        .propertyListings(document_commitment)
+        .call()
+        .await
+        .map_err(|e| e.to_string())?
+        ._0;
+    Ok(Some((claimant, listing)))
+}
+
+/// Find the `PropertyCertificateVerified` event of the document and the receipt of its
+/// transaction, searching from the block the contract was deployed in. The event indexes the
+/// property number and claimant, which narrows the search.
+async fn find_transaction(
+    state: &AppState,
+    job: &Job,
+    certificate: &PropertyOwnershipCertificate,
+    document_commitment: B256,
+) -> Result<VerifiedProperty, String> {
+    let deployment_block = state.deployment_block.ok_or_else(|| {
+        "the block the certificate verifier was deployed in is unknown; set \
+         chain.deployment_block"
+            .to_string()
+    })?;
+    let contract = &state.verifier;
+    let events = contract
+        .PropertyCertificateVerified_filter()
+        .topic1(keccak256(certificate.property_number.as_bytes()))
+        .topic2(job.claimant.into_word())
+        .from_block(deployment_block)
+        .query()
+        .await
+        .map_err(|e| e.to_string())?;
+    let (_, log) = events
+        .into_iter()
+        .find(|(event, _)| event.document_commitment == document_commitment)
+        .ok_or_else(|| {
+            format!(
+                "no PropertyCertificateVerified event since block {}",
+                deployment_block
+            )
+        })?;
+    let transaction_hash = log
+        .transaction_hash
+        .ok_or_else(|| "the PropertyCertificateVerified event has no transaction".to_string())?;
+
+    let receipt = contract
+        .provider()
+        .get_transaction_receipt(transaction_hash)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(VerifiedProperty {
+        document_commitment,
+        transaction_hash: format!("{:?}", transaction_hash),
+        block_number: log.block_number,
+        gas_used: receipt.and_then(|receipt| u64::try_from(receipt.gas_used).ok()),
+    })
+}
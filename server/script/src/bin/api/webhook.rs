@@ -0,0 +1,131 @@
+//! Status update payloads
+//!
+//! The outcome of a property job is reported to the status webhook in the payload version set by
+//! `webhooks.payload_version`:
+//!
+//! - Version 1, the default, has the status, the transaction hash, the property id and number, the
+//!   owner's name and the signature flag.
+//! - Version 2 has a `version` field, the job id, the proof system and program verification key,
+//!   every public value committed in the proof, and the receipt of the transaction with the chain
+//!   id, contract address, block number and gas used, so that the receiver can show a full
+//!   verification receipt and check it on-chain itself.
+
+use alloy::primitives::Address;
+use alloy_sol_types::SolType;
+use serde::Serialize;
+use serde_json::Value;
+use zkpdf_template_lib::PublicValuesStruct;
+
+use crate::error::ErrorBody;
+use crate::jobs::{Job, JobStage, ProofSystem};
+
+/// Event of the status update reporting whether a property proof was stored on-chain.
+pub const STATUS_UPDATE_EVENT: &str = "property.status";
+
+/// Payload version sent when none is configured.
+pub const DEFAULT_PAYLOAD_VERSION: u8 = 1;
+
+/// Payload versions the server can send.
+pub const PAYLOAD_VERSIONS: [u8; 2] = [1, 2];
+
+/// The first status update payload.
+#[derive(Serialize, Debug)]
+struct StatusUpdateV1 {
+    status: bool,
+    transaction_hash: Option<String>,
+    property_id: String,
+    property_number: Option<String>,
+    owner_name: Option<String>,
+    signature_valid: Option<bool>,
+}
+
+/// The status update payload with the public values and the transaction receipt.
+#[derive(Serialize, Debug)]
+struct StatusUpdateV2 {
+    version: u8,
+    event: &'static str,
+    job_id: String,
+    status: bool,
+    property_id: String,
+    proof_system: ProofSystem,
+    /// Verification key of the program, as accepted by the contract.
+    vkey: String,
+    /// Every value committed in the proof, once proven.
+    public_values: Option<Value>,
+    /// The transaction storing the proof, once sent.
+    receipt: Option<Receipt>,
+    /// Whether the document was already verified on-chain, so that `receipt` is the transaction
+    /// that verified it and no proof was generated.
+    already_verified: bool,
+    /// Why the job failed.
+    error: Option<ErrorBody>,
+}
+
+#[derive(Serialize, Debug)]
+struct Receipt {
+    chain_id: u64,
+    contract: Address,
+    transaction_hash: String,
+    block_number: Option<u64>,
+    gas_used: Option<u64>,
+}
+
+/// Where the proof of a job is verified, as reported in version 2 payloads.
+pub struct Verifier<'a> {
+    /// Verification key of the job's program.
+    pub vkey: &'a str,
+    pub chain_id: u64,
+    pub contract: Address,
+}
+
+/// The status update of a finished property job, in payload `version`.
+pub fn status_update(job: &Job, version: u8, verifier: &Verifier) -> Result<Value, String> {
+    // The property id reported to the webhook is the one committed in the proof, not the one
+    // echoed from the request.
+    let public_values = job.public_values.as_deref();
+    let decoded = public_values.and_then(|bytes| PublicValuesStruct::abi_decode(bytes).ok());
+    let property_id = decoded
+        .as_ref()
+        .map_or(job.property_id.clone().unwrap_or_default(), |decoded| {
+            decoded.property_id.clone()
+        });
+    let status = job.stage != JobStage::Failed;
+
+    let payload = match version {
+        1 => serde_json::to_value(StatusUpdateV1 {
+            status,
+            transaction_hash: job.transaction_hash.clone(),
+            property_id,
+            property_number: decoded
+                .as_ref()
+                .map(|decoded| decoded.property_number.clone()),
+            owner_name: decoded.as_ref().map(|decoded| decoded.owner_name.clone()),
+            signature_valid: decoded.as_ref().map(|decoded| decoded.signature_valid),
+        }),
+        2 => serde_json::to_value(StatusUpdateV2 {
+            version,
+            event: STATUS_UPDATE_EVENT,
+            job_id: job.id.clone(),
+            status,
+            property_id,
+            proof_system: job.proof_system,
+            vkey: verifier.vkey.to_string(),
+            public_values: public_values
+                .and_then(|bytes| crate::public_values_json(job.document_type, bytes).ok()),
+            receipt: job
+                .transaction_hash
+                .clone()
+                .map(|transaction_hash| Receipt {
+                    chain_id: verifier.chain_id,
+                    contract: verifier.contract,
+                    transaction_hash,
+                    block_number: job.block_number,
+                    gas_used: job.gas_used,
+                }),
+            already_verified: job.already_verified,
+            error: job.error.clone(),
+        }),
+        _ => return Err(format!("Unsupported webhook payload version {}", version)),
+    };
+    payload.map_err(|e| e.to_string())
+}
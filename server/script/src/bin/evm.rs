@@ -14,17 +14,19 @@
 //! RUST_LOG=info cargo run --release --bin evm -- --system groth16 --pdf-path path/to/your/certificate.pdf
 //! ```
 
+use alloy_primitives::Address;
 use alloy_sol_types::SolType;
 use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
-use sp1_sdk::{
-    include_elf, HashableKey, ProverClient, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey,
-};
+use sp1_sdk::{HashableKey, ProverClient, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
 use std::path::PathBuf;
-use zkpdf_template_lib::PublicValuesStruct;
-
-/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
-pub const ZKPDF_TEMPLATE_ELF: &[u8] = include_elf!("zkpdf-template-program");
+use zkpdf_template_lib::{Claim, DocumentType, ExtractionTemplate, PublicValuesStruct};
+use zkpdf_template_script::{
+    issuer_membership,
+    logging::{self, pii},
+    templates::load_template_file,
+    ZKPDF_TEMPLATE_ELF,
+};
 
 /// The arguments for the EVM command.
 #[derive(Parser, Debug)]
@@ -34,6 +36,17 @@ struct EVMArgs {
     system: ProofSystem,
     #[arg(long, default_value = "../samples/GST-certificate.pdf")]
     pdf_path: String,
+    /// Extraction template (TOML or JSON). Defaults to the built-in Property Ownership template.
+    #[arg(long)]
+    template: Option<String>,
+
+    /// Wallet address of the owner the proof is bound to.
+    #[arg(long, default_value_t = Address::ZERO)]
+    claimant: Address,
+
+    /// Marketplace listing the proof is bound to.
+    #[arg(long, default_value = "")]
+    property_id: String,
 }
 
 /// Enum representing the available proof systems
@@ -50,7 +63,14 @@ struct SP1PropertyProofFixture {
     owner_name: String,
     signature_valid: bool,
     document_commitment: String,
+    commitment_version: u8,
+    property_id: String,
     public_key_hash: String,
+    issuer_root: String,
+    trusted_issuer: bool,
+    template_hash: String,
+    claimant: String,
+    error_code: u8,
     vkey: String,
     public_values: String,
     proof: String,
@@ -58,7 +78,7 @@ struct SP1PropertyProofFixture {
 
 fn main() {
     // Setup the logger.
-    sp1_sdk::utils::setup_logger();
+    logging::init();
 
     // Parse the command line arguments.
     let args = EVMArgs::parse();
@@ -73,13 +93,32 @@ fn main() {
     let pdf_bytes = std::fs::read(&args.pdf_path)
         .unwrap_or_else(|_| panic!("Failed to read PDF file from: {}", args.pdf_path));
 
+    // Load the extraction template
+    let template = match &args.template {
+        Some(path) => load_template_file(path)
+            .unwrap_or_else(|e| panic!("Failed to load template from {}: {}", path, e)),
+        None => ExtractionTemplate::default_for(DocumentType::PropertyOwnership),
+    };
+
     // Setup the inputs.
     let mut stdin = SP1Stdin::new();
     stdin.write(&pdf_bytes);
+    stdin.write(&template);
+    stdin.write(&Claim {
+        claimant: args.claimant.into_array(),
+        property_id: args.property_id.clone(),
+    });
+    stdin.write(
+        &issuer_membership(&pdf_bytes)
+            .unwrap_or_else(|e| panic!("Failed to build trusted issuer input: {}", e)),
+    );
 
-    println!("PDF Path: {}", args.pdf_path);
-    println!("PDF Size: {} bytes", pdf_bytes.len());
-    println!("Proof System: {:?}", args.system);
+    tracing::info!(
+        pdf_path = %args.pdf_path,
+        pdf_size = pdf_bytes.len(),
+        proof_system = ?args.system,
+        "loaded document"
+    );
 
     // Generate the proof based on the selected proof system.
     let proof = match args.system {
@@ -107,7 +146,14 @@ fn create_proof_fixture(
         owner_name: decoded.owner_name,
         signature_valid: decoded.signature_valid,
         document_commitment: format!("0x{}", hex::encode(decoded.document_commitment.as_slice())),
+        commitment_version: decoded.commitment_version,
+        property_id: decoded.property_id,
         public_key_hash: format!("0x{}", hex::encode(decoded.public_key_hash.as_slice())),
+        issuer_root: format!("0x{}", hex::encode(decoded.issuer_root.as_slice())),
+        trusted_issuer: decoded.trusted_issuer,
+        template_hash: format!("0x{}", hex::encode(decoded.template_hash.as_slice())),
+        claimant: decoded.claimant.to_string(),
+        error_code: decoded.error_code,
         vkey: vk.bytes32().to_string(),
         public_values: format!("0x{}", hex::encode(bytes)),
         proof: format!("0x{}", hex::encode(proof.bytes())),
@@ -115,17 +161,24 @@ fn create_proof_fixture(
 
     // The verification key is used to verify that the proof corresponds to the execution of the
     // program on the given input.
-    println!("Verification Key: {}", fixture.vkey);
-    println!(
-        "Property Number: {}\nOwner Name: {}\nSignature Valid: {}\nDocument Commitment: {}\nPublic Key Hash: {}",
-        fixture.property_number,
-        fixture.owner_name,
-        fixture.signature_valid,
-        fixture.document_commitment,
-        fixture.public_key_hash
+    // The public values and proof are written to the fixture file; the owner's name and
+    // property number are only logged with LOG_PII=true.
+    tracing::info!(
+        vkey = %fixture.vkey,
+        property_number = %pii(&fixture.property_number),
+        owner_name = %pii(&fixture.owner_name),
+        signature_valid = fixture.signature_valid,
+        document_commitment = %fixture.document_commitment,
+        commitment_version = fixture.commitment_version,
+        property_id = %fixture.property_id,
+        public_key_hash = %fixture.public_key_hash,
+        issuer_root = %fixture.issuer_root,
+        trusted_issuer = fixture.trusted_issuer,
+        template_hash = %fixture.template_hash,
+        claimant = %fixture.claimant,
+        error_code = fixture.error_code,
+        "proof generated"
     );
-    println!("Public Values: {}", fixture.public_values);
-    println!("Proof Bytes: {}", fixture.proof);
 
     // Save the fixture to a file.
     let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../contracts/src/fixtures");
@@ -0,0 +1,38 @@
+//! Print the signer key hash of a signed PDF, to add its issuer to `lib/issuers/registry.toml`.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --release --bin issuer-key -- --pdf-path path/to/signed.pdf
+//! ```
+
+use clap::Parser;
+use zkpdf_template_lib::{issuers::signer_public_key_hash, IssuerRegistry};
+use zkpdf_template_script::logging;
+
+/// The arguments for the command.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[arg(long)]
+    pdf_path: String,
+}
+
+fn main() {
+    logging::init();
+    let args = Args::parse();
+
+    let pdf_bytes = std::fs::read(&args.pdf_path)
+        .unwrap_or_else(|_| panic!("Failed to read PDF file from: {}", args.pdf_path));
+
+    let public_key_hash =
+        signer_public_key_hash(pdf_bytes).expect("failed to verify the PDF signature");
+
+    let registry = IssuerRegistry::builtin();
+    let trusted = registry
+        .key_hashes()
+        .expect("failed to parse the issuer registry")
+        .contains(&public_key_hash);
+
+    println!("public_key_hash = \"0x{}\"", hex::encode(public_key_hash));
+    println!("Trusted issuer: {}", trusted);
+}
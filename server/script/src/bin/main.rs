@@ -13,14 +13,24 @@
 //! ```shell
 //! RUST_LOG=info cargo run --release -- --execute --pdf-path path/to/your/certificate.pdf
 //! ```
+//! and a custom extraction template:
+//! ```shell
+//! RUST_LOG=info cargo run --release -- --execute --template ../lib/templates/property_ownership_labelled.toml
+//! ```
 
+use alloy_primitives::Address;
 use alloy_sol_types::SolType;
 use clap::Parser;
-use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
-use zkpdf_template_lib::PublicValuesStruct;
-
-/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
-pub const ZKPDF_TEMPLATE_ELF: &[u8] = include_elf!("zkpdf-template-program");
+use sp1_sdk::{ProverClient, SP1Stdin};
+use zkpdf_template_lib::{
+    utils::GSTVerificationError, Claim, DocumentType, ExtractionTemplate, PublicValuesStruct,
+};
+use zkpdf_template_script::{
+    issuer_membership,
+    logging::{self, pii},
+    templates::load_template_file,
+    ZKPDF_TEMPLATE_ELF,
+};
 
 /// The arguments for the command.
 #[derive(Parser, Debug)]
@@ -34,18 +44,30 @@ struct Args {
 
     #[arg(long, default_value = "../samples/GST-certificate.pdf")]
     pdf_path: String,
+
+    /// Extraction template (TOML or JSON). Defaults to the built-in Property Ownership template.
+    #[arg(long)]
+    template: Option<String>,
+
+    /// Wallet address of the owner the proof is bound to.
+    #[arg(long, default_value_t = Address::ZERO)]
+    claimant: Address,
+
+    /// Marketplace listing the proof is bound to.
+    #[arg(long, default_value = "")]
+    property_id: String,
 }
 
 fn main() {
     // Setup the logger.
-    sp1_sdk::utils::setup_logger();
     dotenv::dotenv().ok();
+    logging::init();
 
     // Parse the command line arguments.
     let args = Args::parse();
 
     if args.execute == args.prove {
-        eprintln!("Error: You must specify either --execute or --prove");
+        tracing::error!("You must specify either --execute or --prove");
         std::process::exit(1);
     }
 
@@ -56,17 +78,38 @@ fn main() {
     let pdf_bytes = std::fs::read(&args.pdf_path)
         .unwrap_or_else(|_| panic!("Failed to read PDF file from: {}", args.pdf_path));
 
+    // Load the extraction template
+    let template = match &args.template {
+        Some(path) => load_template_file(path)
+            .unwrap_or_else(|e| panic!("Failed to load template from {}: {}", path, e)),
+        None => ExtractionTemplate::default_for(DocumentType::PropertyOwnership),
+    };
+
     // Setup the inputs.
     let mut stdin = SP1Stdin::new();
     stdin.write(&pdf_bytes);
-
-    println!("PDF Path: {}", args.pdf_path);
-    println!("PDF Size: {} bytes", pdf_bytes.len());
+    stdin.write(&template);
+    stdin.write(&Claim {
+        claimant: args.claimant.into_array(),
+        property_id: args.property_id.clone(),
+    });
+    stdin.write(
+        &issuer_membership(&pdf_bytes)
+            .unwrap_or_else(|e| panic!("Failed to build trusted issuer input: {}", e)),
+    );
+
+    tracing::info!(
+        pdf_path = %args.pdf_path,
+        pdf_size = pdf_bytes.len(),
+        template = %template.id,
+        claimant = %args.claimant,
+        "loaded document"
+    );
 
     if args.execute {
         // Execute the program
         let (output, report) = client.execute(ZKPDF_TEMPLATE_ELF, &stdin).run().unwrap();
-        println!("Program executed successfully.");
+        tracing::info!("Program executed successfully.");
 
         // Read the output.
         let decoded = PublicValuesStruct::abi_decode(output.as_slice()).unwrap();
@@ -75,22 +118,43 @@ fn main() {
             owner_name,
             signature_valid,
             document_commitment,
+            commitment_version,
+            property_id,
             public_key_hash,
+            issuer_root,
+            trusted_issuer,
+            template_hash,
+            claimant,
+            error_code,
         } = decoded;
-        println!("Property Number: {}", property_number);
-        println!("Owner Name: {}", owner_name);
-        println!("Signature Valid: {}", signature_valid);
-        println!(
-            "Document Commitment: 0x{}",
-            hex::encode(document_commitment.as_ref() as &[u8])
-        );
-        println!(
-            "Public Key Hash: 0x{}",
-            hex::encode(public_key_hash.as_ref() as &[u8])
+        if error_code != 0 {
+            tracing::warn!(
+                error_code,
+                "Document rejected: {}",
+                GSTVerificationError::describe_code(error_code)
+            );
+        }
+        // Owner names and property numbers are only logged with LOG_PII=true
+        tracing::info!(
+            property_number = %pii(&property_number),
+            owner_name = %pii(&owner_name),
+            signature_valid,
+            document_commitment = %document_commitment,
+            commitment_version,
+            public_key_hash = %public_key_hash,
+            issuer_root = %issuer_root,
+            trusted_issuer,
+            template_hash = %template_hash,
+            property_id = %property_id,
+            claimant = %claimant,
+            "public values"
         );
 
         // Record the number of cycles executed.
-        println!("Number of cycles: {}", report.total_instruction_count());
+        tracing::info!(
+            cycles = report.total_instruction_count(),
+            "Number of cycles"
+        );
     } else {
         // Setup the program for proving.
         let (pk, vk) = client.setup(ZKPDF_TEMPLATE_ELF);
@@ -101,10 +165,10 @@ fn main() {
             .run()
             .expect("failed to generate proof");
 
-        println!("Successfully generated proof!");
+        tracing::info!("Successfully generated proof!");
 
         // Verify the proof.
         client.verify(&proof, &vk).expect("failed to verify proof");
-        println!("Successfully verified proof!");
+        tracing::info!("Successfully verified proof!");
     }
 }
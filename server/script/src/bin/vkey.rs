@@ -1,10 +1,33 @@
-use sp1_sdk::{include_elf, HashableKey, Prover, ProverClient};
-
-/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
-pub const ZKPDF_TEMPLATE_ELF: &[u8] = include_elf!("zkpdf-template-program");
+use sp1_sdk::{HashableKey, Prover, ProverClient};
+use zkpdf_template_lib::{DocumentType, IssuerRegistry};
+use zkpdf_template_script::{
+    logging, program_elf,
+    templates::{TemplateRegistry, DEFAULT_TEMPLATES_DIR},
+};
 
 fn main() {
+    logging::init();
+
     let prover = ProverClient::builder().cpu().build();
-    let (_, vk) = prover.setup(ZKPDF_TEMPLATE_ELF);
-    println!("{}", vk.bytes32());
+    for document_type in DocumentType::ALL {
+        let (_, vk) = prover.setup(program_elf(document_type));
+        println!("{}: {}", document_type.name(), vk.bytes32());
+    }
+
+    // The template hashes to approve on-chain with `setTemplateApproval`.
+    let templates =
+        TemplateRegistry::load_dir(DEFAULT_TEMPLATES_DIR).expect("failed to load templates");
+    for template in templates.iter() {
+        println!(
+            "template {}: 0x{}",
+            template.id,
+            hex::encode(template.hash())
+        );
+    }
+
+    // The trusted issuer root to approve on-chain with `setTrustedIssuerRoot`.
+    let issuer_root = IssuerRegistry::builtin()
+        .root()
+        .expect("failed to build trusted issuer root");
+    println!("issuer root: 0x{}", hex::encode(issuer_root));
 }
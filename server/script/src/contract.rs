@@ -0,0 +1,117 @@
+//! Typed client of the `CertificateVerifier` contract.
+//!
+//! The bindings are generated from the ABI committed in `contracts/abi/CertificateVerifier.json`
+//! rather than from a hand-written interface, so that every function and event of the contract
+//! is available and the bindings cannot drift from it: the `Contract ABI` workflow
+//! (`.github/workflows/contract-abi.yml` at the repository root) checks the committed ABI against
+//! the one Foundry builds. After changing the contract, regenerate it from `contracts/` with
+//!
+//! ```bash
+//! forge inspect src/PropertyVerifier.sol:CertificateVerifier abi --json > abi/CertificateVerifier.json
+//! ```
+//!
+//! `CertificateVerifier::new(address, provider)` returns an instance with a method per function,
+//! e.g. `isPropertyVerified(commitment).call()` or `verifyAndStoreProperty(values, proof).send()`,
+//! and a `<Event>_filter()` per event to query its logs.
+
+use alloy::rpc::types::Log;
+
+alloy::sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    #[derive(Debug)]
+    CertificateVerifier,
+    "../contracts/abi/CertificateVerifier.json"
+);
+
+pub use CertificateVerifier::{
+    CertificateVerifierEvents, CertificateVerifierInstance, GSTCertificateVerified,
+    PropertyCertificateVerified, TemplateApprovalUpdated, TrustedIssuerRootUpdated,
+};
+
+/// Decode a `GSTCertificateVerified` log. Returns `None` for logs of other events.
+pub fn decode_gst_verified(log: &Log) -> Option<GSTCertificateVerified> {
+    log.log_decode::<GSTCertificateVerified>()
+        .ok()
+        .map(|log| log.inner.data)
+}
+
+/// Decode a `PropertyCertificateVerified` log. Returns `None` for logs of other events.
+pub fn decode_property_verified(log: &Log) -> Option<PropertyCertificateVerified> {
+    log.log_decode::<PropertyCertificateVerified>()
+        .ok()
+        .map(|log| log.inner.data)
+}
+
+/// Decode a log of any event of the contract, e.g. from a receipt.
+pub fn decode_event(log: &Log) -> Option<CertificateVerifierEvents> {
+    use alloy::sol_types::SolEventInterface;
+
+    CertificateVerifierEvents::decode_log(&log.inner, true)
+        .ok()
+        .map(|log| log.data)
+}
+
+alloy::sol! {
+    /// Custom errors of the SP1 verifier gateway and verifiers the contract calls, from
+    /// `sp1-contracts`. Their reverts are bubbled up by the certificate verifier.
+    #[derive(Debug)]
+    interface ISP1VerifierErrors {
+        error WrongVerifierSelector(bytes4 received, bytes4 expected);
+        error InvalidProof();
+        error ProofInvalid();
+        error PublicInputNotInField();
+        error RouteNotFound(bytes4 selector);
+        error RouteIsFrozen(bytes4 selector);
+    }
+}
+
+/// Describe why a call to the contract reverted, from the revert data returned by the node.
+///
+/// The `require` messages of the contract and the errors of the SP1 verifiers are decoded; other
+/// data is returned as hex.
+pub fn describe_revert(data: &[u8]) -> String {
+    use alloy::sol_types::{Panic, PanicKind, Revert, SolError, SolInterface};
+    use ISP1VerifierErrors::ISP1VerifierErrorsErrors as Sp1Error;
+
+    if let Ok(revert) = Revert::abi_decode(data, true) {
+        return revert.reason;
+    }
+    if let Ok(panic) = Panic::abi_decode(data, true) {
+        return match panic.kind() {
+            // `SP1MockVerifier` asserts that the proof is empty
+            Some(PanicKind::Assert) => {
+                "The verifier rejected the proof: the mock verifier only accepts mock proofs"
+                    .to_string()
+            }
+            Some(kind) => format!("The contract panicked: {}", kind),
+            None => format!("The contract panicked with code {}", panic.code),
+        };
+    }
+    match Sp1Error::abi_decode(data, true) {
+        Ok(Sp1Error::WrongVerifierSelector(error)) => format!(
+            "The proof is for the SP1 verifier {}, but the contract verifies with {}: the \
+             prover and the deployed verifier use different SP1 versions",
+            error.received, error.expected
+        ),
+        Ok(Sp1Error::InvalidProof(_)) | Ok(Sp1Error::ProofInvalid(_)) => {
+            "The SP1 verifier rejected the proof: it was not generated for the program \
+             verification key and public values of the call"
+                .to_string()
+        }
+        Ok(Sp1Error::PublicInputNotInField(_)) => {
+            "The SP1 verifier rejected the proof: a public input is not in the field".to_string()
+        }
+        Ok(Sp1Error::RouteNotFound(error)) => format!(
+            "The SP1 verifier gateway has no verifier for proofs with selector {}: the SP1 \
+             version of the prover is not supported on this chain",
+            error.selector
+        ),
+        Ok(Sp1Error::RouteIsFrozen(error)) => format!(
+            "The SP1 verifier for proofs with selector {} is frozen",
+            error.selector
+        ),
+        Err(_) if data.is_empty() => "The call reverted without a reason".to_string(),
+        Err(_) => format!("The call reverted with data 0x{}", hex::encode(data)),
+    }
+}
@@ -0,0 +1,47 @@
+//! Shared helpers for the zkPDF template binaries.
+//!
+//! The program ELFs are registered here by document type so that the CLI, the EVM fixture
+//! generator, the vkey tool and the API all prove documents with the same programs.
+
+use sp1_sdk::include_elf;
+use zkpdf_template_lib::{
+    issuers::signer_public_key_hash, DocumentType, IssuerMembership, IssuerRegistry,
+};
+
+pub mod contract;
+pub mod logging;
+pub mod templates;
+
+#[cfg(test)]
+mod tests;
+
+/// The ELF (executable and linkable format) file for the Property Ownership program.
+pub const ZKPDF_TEMPLATE_ELF: &[u8] = include_elf!("zkpdf-template-program");
+
+/// The ELF file for the GST certificate program.
+pub const ZKPDF_GST_ELF: &[u8] = include_elf!("zkpdf-template-gst-program");
+
+/// Look up the program ELF registered for a document type.
+pub fn program_elf(document_type: DocumentType) -> &'static [u8] {
+    match document_type {
+        DocumentType::Gst => ZKPDF_GST_ELF,
+        DocumentType::PropertyOwnership => ZKPDF_TEMPLATE_ELF,
+    }
+}
+
+/// Build the trusted issuer input for a document from the built-in issuer registry.
+///
+/// The signer of a document outside the registry gets an empty proof; the program then commits
+/// `trusted_issuer = false`. A document whose signature cannot be verified gets the root alone,
+/// so that the program reports why it was rejected.
+pub fn issuer_membership(pdf_bytes: &[u8]) -> Result<IssuerMembership, String> {
+    let registry = IssuerRegistry::builtin();
+    match signer_public_key_hash(pdf_bytes.to_vec()) {
+        Ok(public_key_hash) => registry.membership(public_key_hash),
+        Err(_) => registry.root().map(|root| IssuerMembership {
+            root,
+            proof: Vec::new(),
+        }),
+    }
+    .map_err(|e| e.to_string())
+}
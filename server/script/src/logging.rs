@@ -0,0 +1,86 @@
+//! Logging
+//!
+//! Every binary logs through `tracing`. [`init`] writes JSON lines to stderr, or human-readable
+//! lines with `LOG_FORMAT=text`, filtered by `RUST_LOG` (default `info`). The API server adds a
+//! span per request and per job, so every line carries the `request_id` or `job_id` it belongs to.
+//!
+//! Logs must not contain secrets or personal data:
+//!
+//! - Keys and passwords are never logged, only the name of the variable or file holding them.
+//!   URLs, which often embed API keys, are logged with [`redact_url`].
+//! - Owner names, legal names and property or GST numbers are logged through [`pii`], which
+//!   replaces them with `[redacted]` unless `LOG_PII=true` is set for debugging.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable selecting `json` (the default) or `text` log lines.
+pub const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+/// Environment variable allowing personal data in logs when `true`.
+pub const LOG_PII_ENV: &str = "LOG_PII";
+
+/// Placeholder logged instead of a redacted value.
+pub const REDACTED: &str = "[redacted]";
+
+/// Install the log subscriber. Does nothing if one is already installed.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    let _ = match std::env::var(LOG_FORMAT_ENV).as_deref() {
+        Ok("text") => subscriber.try_init(),
+        _ => subscriber.json().with_current_span(true).try_init(),
+    };
+}
+
+/// Whether personal data may be logged, read once from `LOG_PII`.
+pub fn pii_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var(LOG_PII_ENV).is_ok_and(|value| value == "true" || value == "1")
+    })
+}
+
+/// Personal data, displayed as `[redacted]` unless `LOG_PII` is set.
+pub struct Pii<T>(T);
+
+/// Wrap personal data for logging.
+pub fn pii<T: fmt::Display>(value: T) -> Pii<T> {
+    Pii(value)
+}
+
+impl<T: fmt::Display> fmt::Display for Pii<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if pii_enabled() {
+            self.0.fmt(f)
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+/// The scheme and host of a URL, without the credentials, path and query that may hold an API key.
+pub fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => match parsed.host_str() {
+            Some(host) => format!("{}://{}", parsed.scheme(), host),
+            None => REDACTED.to_string(),
+        },
+        Err(_) => REDACTED.to_string(),
+    }
+}
+
+/// Replace `url` in an error message with its [`redact_url`] form. HTTP client errors quote the
+/// full URL of the failed request.
+pub fn redact_url_in(message: &str, url: &str) -> String {
+    let redacted = redact_url(url);
+    let mut message = message.replace(url, &redacted);
+    if let Ok(parsed) = reqwest::Url::parse(url) {
+        message = message.replace(parsed.as_str(), &redacted);
+    }
+    message
+}
@@ -0,0 +1,99 @@
+//! Extraction template registry
+//!
+//! Templates are loaded from TOML or JSON files so that new issuer layouts can be onboarded
+//! without rebuilding the zkVM programs. The built-in template of every document type is always
+//! registered.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+use zkpdf_template_lib::{DocumentType, ExtractionTemplate};
+
+/// Default directory holding extraction templates, relative to the `script` directory.
+pub const DEFAULT_TEMPLATES_DIR: &str = "../lib/templates";
+
+/// Extraction templates keyed by template id.
+#[derive(Debug, Clone)]
+pub struct TemplateRegistry {
+    templates: BTreeMap<String, ExtractionTemplate>,
+}
+
+impl TemplateRegistry {
+    /// A registry containing only the built-in templates.
+    pub fn with_defaults() -> Self {
+        let templates = DocumentType::ALL
+            .into_iter()
+            .map(ExtractionTemplate::default_for)
+            .map(|template| (template.id.clone(), template))
+            .collect();
+        Self { templates }
+    }
+
+    /// A registry containing the built-in templates and every template file in `dir`.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let mut registry = Self::with_defaults();
+        let dir = dir.as_ref();
+        if !dir.exists() {
+            return Ok(registry);
+        }
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("toml" | "json")
+            ) {
+                registry.insert(load_template_file(&path)?);
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Register a template, replacing any template with the same id.
+    pub fn insert(&mut self, template: ExtractionTemplate) {
+        self.templates.insert(template.id.clone(), template);
+    }
+
+    /// Look up a template by id.
+    pub fn get(&self, id: &str) -> Option<&ExtractionTemplate> {
+        self.templates.get(id)
+    }
+
+    /// Resolve the template to prove a document with: the requested template if an id is
+    /// given, the built-in template of the document type otherwise.
+    pub fn resolve(
+        &self,
+        document_type: DocumentType,
+        id: Option<&str>,
+    ) -> Result<ExtractionTemplate, String> {
+        let template = match id {
+            Some(id) => self
+                .get(id)
+                .cloned()
+                .ok_or_else(|| format!("Unknown extraction template: {}", id))?,
+            None => ExtractionTemplate::default_for(document_type),
+        };
+        template
+            .check_document_type(document_type)
+            .map_err(|e| e.to_string())?;
+        Ok(template)
+    }
+
+    /// Iterate over all registered templates.
+    pub fn iter(&self) -> impl Iterator<Item = &ExtractionTemplate> {
+        self.templates.values()
+    }
+}
+
+/// Load a single template from a `.toml` or `.json` file.
+pub fn load_template_file(path: impl AsRef<Path>) -> Result<ExtractionTemplate, Box<dyn Error>> {
+    let path = path.as_ref();
+    let input = std::fs::read_to_string(path)?;
+    let template = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => ExtractionTemplate::from_json(&input)?,
+        _ => ExtractionTemplate::from_toml(&input)?,
+    };
+    Ok(template)
+}
@@ -1,26 +1,65 @@
+import { createHmac, timingSafeEqual } from "crypto";
 import { NextRequest, NextResponse } from "next/server";
 import pool from "../../../libs/db";
 
+// Status updates older than this are rejected, so that a captured request cannot be replayed later
+const MAX_SIGNATURE_AGE_SECONDS = 5 * 60;
+
+// Check the signature of the proving server: HMAC-SHA256 of `<timestamp>.<body>` keyed with the
+// shared WEBHOOK_SECRET
+function verifySignature(request: NextRequest, body: string): boolean {
+    const secret = process.env.WEBHOOK_SECRET;
+    const timestamp = request.headers.get("x-webhook-timestamp");
+    const signature = request.headers.get("x-webhook-signature");
+    if (!secret || !timestamp || !signature) {
+        return false;
+    }
+
+    const age = Math.abs(Date.now() / 1000 - Number(timestamp));
+    if (!Number.isFinite(age) || age > MAX_SIGNATURE_AGE_SECONDS) {
+        return false;
+    }
+
+    const expected =
+        "sha256=" + createHmac("sha256", secret).update(`${timestamp}.${body}`).digest("hex");
+    return (
+        expected.length === signature.length &&
+        timingSafeEqual(Buffer.from(expected), Buffer.from(signature))
+    );
+}
+
 interface WebhookPayload {
-    status: string;
+    status: boolean;
     transaction_hash?: string | null;
     property_id: string | number;
 }
 
 export async function POST(request: NextRequest) {
     try {
-        const body: WebhookPayload = await request.json();
+        const rawBody = await request.text();
+        if (!verifySignature(request, rawBody)) {
+            return NextResponse.json(
+                {
+                    success: false,
+                    error: "Invalid webhook signature",
+                },
+                { status: 401 }
+            );
+        }
+
+        // Retries of a delivery carry the same X-Webhook-Id; the updates below are idempotent, so
+        // a repeated delivery leaves the property as it is.
+        const body: WebhookPayload = JSON.parse(rawBody);
         const { status, transaction_hash, property_id } = body;
 
         console.log(body);
 
-        //
-        // Validate required fields
-        https: if (!status || !property_id) {
+        // Validate required fields; `status` is false when proving or storing the proof failed
+        if (typeof status !== "boolean" || !property_id) {
             return NextResponse.json(
                 {
                     success: false,
-                    error: "Missing required fields: status, propertyId",
+                    error: "Missing required fields: status, property_id",
                 },
                 { status: 400 }
             );
@@ -74,23 +113,42 @@ export async function POST(request: NextRequest) {
                 { status: 200 }
             );
         } else {
-            // For failed/other statuses, just log the attempt (optional: store failed transaction hash if provided)
+            // A failed job says nothing about the listing: anyone can queue a job that fails for
+            // any property id. Verification is only cleared when the reported transaction is the
+            // one the listing was verified with.
+            console.warn(
+                `Verification failed for property ${property_id}` +
+                    (transaction_hash ? ` (transaction ${transaction_hash})` : "")
+            );
+
+            if (!transaction_hash) {
+                return NextResponse.json(
+                    {
+                        success: true,
+                        message: "Verification failure logged",
+                    },
+                    { status: 200 }
+                );
+            }
+
             const updateQuery = `
                 UPDATE properties
                 SET is_verified = false,
-                    verification_transaction_hash = $1,
                     updated_at = CURRENT_TIMESTAMP
-                WHERE id = $2
+                WHERE id = $1 AND verification_transaction_hash = $2
                 RETURNING id, is_verified, verification_transaction_hash
             `;
 
-            const result = await pool.query(updateQuery, [transaction_hash || null, property_id]);
+            const result = await pool.query(updateQuery, [property_id, transaction_hash]);
 
             return NextResponse.json(
                 {
                     success: true,
-                    message: `Verification attempt logged with status: ${status}`,
-                    data: result.rows[0],
+                    message:
+                        result.rowCount === 0
+                            ? "Verification failure logged"
+                            : "Property verification revoked",
+                    data: result.rows[0] ?? null,
                 },
                 { status: 200 }
             );
@@ -1,6 +1,64 @@
 "use client";
 import { loadWasm } from "@/app/libs/wasm";
 import React, { useState, useCallback, useMemo } from "react";
+import { BrowserProvider } from "ethers";
+import { useAuth } from "@/app/hook/useAuth";
+
+const PROOF_API_BASE_URL =
+    process.env.NEXT_PUBLIC_PROOF_API_BASE_URL ||
+    "https://rhode-leslie-bags-chicken.trycloudflare.com";
+
+// POST a PDF and form fields to the prover API, throwing its error message on failure
+async function postDocument(path: string, data: any, fields: Record<string, string>) {
+    const form = new FormData();
+    form.append(
+        "pdf",
+        new Blob([data.buffer], { type: "application/pdf" }),
+        data.file?.name || "document.pdf"
+    );
+    for (const [name, value] of Object.entries(fields)) {
+        form.append(name, value);
+    }
+
+    const response = await fetch(`${PROOF_API_BASE_URL}${path}`, { method: "POST", body: form });
+    if (!response.ok) {
+        const error = await response.json().catch(() => null);
+        throw new Error(error?.message || `Status ${response.status}`);
+    }
+    return response.json();
+}
+
+const JOB_POLL_INTERVAL_MS = 5000;
+const FINISHED_JOB_STAGES = ["notified", "completed", "failed"];
+
+// Poll a proving job until it finishes, reporting each stage, and throw its error if it failed
+async function waitForJob(jobId: string, onStage: (stage: string) => void) {
+    for (;;) {
+        const response = await fetch(`${PROOF_API_BASE_URL}/jobs/${jobId}`);
+        const job = await response.json().catch(() => null);
+        if (!response.ok) {
+            throw new Error(job?.message || `Status ${response.status}`);
+        }
+        onStage(job.stage);
+        if (job.stage === "failed") {
+            throw new Error(job.error?.message || "Proof generation failed");
+        }
+        if (FINISHED_JOB_STAGES.includes(job.stage)) {
+            return job;
+        }
+        await new Promise((resolve) => setTimeout(resolve, JOB_POLL_INTERVAL_MS));
+    }
+}
+
+// Sign the claim message of a document with the owner's wallet (EIP-191 personal_sign)
+async function signClaimMessage(walletAddress: string, message: string) {
+    if (typeof window === "undefined" || !(window as any).ethereum) {
+        throw new Error("MetaMask is not installed. Connect your wallet to prove ownership.");
+    }
+    const provider = new BrowserProvider((window as any).ethereum);
+    const signer = await provider.getSigner(walletAddress);
+    return signer.signMessage(message);
+}
 
 // Simple PDF Drop Zone Component
 export function PDFDropZone({
@@ -223,7 +281,8 @@ export function PropertyDocumentUpload({ onDocumentVerified, propertyId }: any)
     const [proofData, setProofData] = useState<string | null>(null);
     const [proofLoading, setProofLoading] = useState(false);
     const [proofError, setProofError] = useState<string | null>(null);
-
+    const [proofStage, setProofStage] = useState<string | null>(null);
+    const { user } = useAuth();
 
     const handleFileProcessed = useCallback(
         async (data: any) => {
@@ -232,24 +291,37 @@ export function PropertyDocumentUpload({ onDocumentVerified, propertyId }: any)
 
             // Automatically generate proof if document is valid
             if (data.isValid && data.buffer) {
-                setProofLoading(true);
                 setProofError(null);
                 setProofData(null);
 
+                // The proof is bound to the wallet, so there is nothing to prove without one
+                const walletAddress = (user as any)?.walletAddress;
+                if (!walletAddress) {
+                    setProofError("Connect your wallet to prove ownership of this document.");
+                    return;
+                }
+
+                setProofLoading(true);
+                setProofStage(null);
                 try {
-                    const response = await fetch(`${process.env.NEXT_PUBLIC_PROOF_API_BASE_URL || 'https://rhode-leslie-bags-chicken.trycloudflare.com'}/prove`, {
-                        method: "POST",
-                        headers: { "Content-Type": "application/json" },
-                        body: JSON.stringify({
-                            pdf_bytes: Array.from(data.buffer),
-                            property_id: propertyId?.toString() || "1"
-                        }),
+                    const listing = propertyId?.toString() || "1";
+
+                    // The wallet signs the document commitment and listing to claim the document
+                    const { message } = await postDocument("/commitment", data, {
+                        property_id: listing,
                     });
+                    const walletSignature = await signClaimMessage(walletAddress, message);
 
-                    if (!response.ok) throw new Error(`Status ${response.status}`);
+                    const queuedJob = await postDocument("/prove", data, {
+                        property_id: listing,
+                        wallet_address: walletAddress,
+                        wallet_signature: walletSignature,
+                    });
+                    setProofStage(queuedJob.stage);
 
-                    const proofResult = await response.json();
-                    setProofData(JSON.stringify(proofResult, null, 2));
+                    // Proving and storing the proof on-chain take minutes
+                    const job = await waitForJob(queuedJob.id, setProofStage);
+                    setProofData(JSON.stringify(job, null, 2));
                 } catch (error: any) {
                     if (error.message.includes("fetch") || error.message.includes("Failed to fetch")) {
                         setProofError("Prover API not running. Start the prover server.");
@@ -261,7 +333,7 @@ export function PropertyDocumentUpload({ onDocumentVerified, propertyId }: any)
                 }
             }
         },
-        [onDocumentVerified, propertyId]
+        [onDocumentVerified, propertyId, user]
     );
 
     return (
@@ -301,7 +373,9 @@ export function PropertyDocumentUpload({ onDocumentVerified, propertyId }: any)
                                 d="M4 12a8 8 0 018-8v4a4 4 0 00-4 4H4z"
                             ></path>
                         </svg>
-                        <span className="text-blue-800 font-medium">Generating zkProof...</span>
+                        <span className="text-blue-800 font-medium">
+                            Generating zkProof...{proofStage && ` (${proofStage})`}
+                        </span>
                     </div>
                 </div>
             )}
@@ -315,7 +389,7 @@ export function PropertyDocumentUpload({ onDocumentVerified, propertyId }: any)
 
             {proofData && (
                 <div className="bg-gray-50 border border-gray-200 rounded-lg p-4">
-                    <p className="text-gray-800 font-medium mb-2">Generated zkProof:</p>
+                    <p className="text-gray-800 font-medium mb-2">zkProof Job:</p>
                     <pre className="text-xs bg-gray-100 p-2 rounded overflow-auto max-h-32">
                         {proofData}
                     </pre>